@@ -0,0 +1,89 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use monirs::parts::filesearcher::FileSearcherBuilder;
+
+/// Files of the generated tree, MONI_BENCH_FILES to change it.
+const DEFAULT_FILES: usize = 20_000;
+const DIRS_PER_LEVEL: usize = 10;
+const FILES_PER_DIR: usize = 20;
+
+/// A tree like a monorepo, `FILES_PER_DIR` files in each directory, three levels deep.
+fn generate_tree(files: usize) -> PathBuf {
+    let root = env::temp_dir().join(format!("monirs-bench-{}", files));
+    if root.exists() {
+        return root;
+    }
+    let tmp_root = root.with_extension("tmp");
+    let _ = fs::remove_dir_all(&tmp_root);
+    let mut written = 0;
+    'tree: for a in 0..DIRS_PER_LEVEL {
+        for b in 0..DIRS_PER_LEVEL {
+            for c in 0..DIRS_PER_LEVEL {
+                let dir = tmp_root.join(format!("pkg{}/src{}/mod{}", a, b, c));
+                fs::create_dir_all(&dir).unwrap();
+                for i in 0..FILES_PER_DIR {
+                    let extension = if i % 4 == 0 { "md" } else { "rs" };
+                    fs::write(dir.join(format!("file{}.{}", i, extension)), "fn main() {}")
+                        .unwrap();
+                    written += 1;
+                    if written == files {
+                        break 'tree;
+                    }
+                }
+            }
+        }
+    }
+    fs::create_dir_all(tmp_root.join("target/debug")).unwrap();
+    fs::rename(&tmp_root, &root).unwrap();
+    root
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let files = env::var("MONI_BENCH_FILES")
+        .ok()
+        .and_then(|files| files.parse().ok())
+        .unwrap_or(DEFAULT_FILES);
+    let root = generate_tree(files);
+    let root = root.to_str().unwrap();
+    let searcher = FileSearcherBuilder::new()
+        .root(root)
+        .target_extension("rs")
+        .ignore_re("^target$")
+        .build();
+    let mut group = c.benchmark_group(format!("scan {} files", files));
+    group.sample_size(10);
+    let mut threads = vec![1, rayon::current_num_threads()];
+    threads.dedup();
+    for threads in threads {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("get_all_filenames", threads),
+            &threads,
+            |b, _| b.iter(|| pool.install(|| searcher.get_all_filenames())),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("for_each_file", threads),
+            &threads,
+            |b, _| {
+                b.iter(|| {
+                    pool.install(|| {
+                        searcher.for_each_file(|path| {
+                            criterion::black_box(Path::new(&path));
+                        })
+                    })
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_scan);
+criterion_main!(benches);
@@ -1,4 +1,9 @@
 //use moni.json
 fn main() {
-    monirs::moni::monitaring_from_json();
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>()[..] {
+        ["ls"] | ["--dry-run"] => monirs::moni::ls_from_json(),
+        ["explain", path] => monirs::moni::explain_from_json(path),
+        _ => monirs::moni::monitaring_from_json(),
+    }
 }
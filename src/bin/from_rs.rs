@@ -1,18 +1,19 @@
 use std::{
     fs::File,
     io::{BufReader, Read},
+    path::Path,
 };
 
 use monirs::parts::debuger::{DefaultMoniDebugMessage, MoniDebuger};
 
 fn main() {
-    let exe_fn = |filepath: &str| -> Result<String, String> {
+    let exe_fn = |filepath: &Path| -> Result<String, String> {
         let mut reader = BufReader::new(File::open(filepath).unwrap());
         let mut content = String::new();
         match reader.read_to_string(&mut content) {
             Err(e) => Err(e.to_string()),
             _ => {
-                let frist_line = format!("file path is \n{}\n", filepath);
+                let frist_line = format!("file path is \n{}\n", filepath.display());
                 let second_line = format!("file content is \n{}\n", content);
                 Ok(format!("{}\n{}\n", frist_line, second_line))
             }
@@ -1,9 +1,6 @@
-use monirs::parts::filesearcher::FileSearcherBuilder;
+use monirs::moni::MoniBuilder;
 
 fn main() {
-    let filesearcher = FileSearcherBuilder::new()
-        .root("./")
-        .target_extension("rs")
-        .build();
-    println!("{:#?}", filesearcher.get_all_files());
+    let builder = MoniBuilder::new().root("./").target_extension("rs");
+    print!("{}", builder.ls());
 }
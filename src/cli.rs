@@ -1,10 +1,22 @@
-use clap::Parser;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{Parser, Subcommand};
 
 use crate::{
     configs::moni_config::MoniConfig,
+    moni::RunOnStart,
     parts::{
-        debuger::{DefaultMoniDebugMessage, MoniDebuger},
+        color::ColorMode,
+        content_filter::ContentFilter,
+        debuger::{DefaultMoniDebugMessage, MoniDebuger, MoniOutput},
         moni_execute_command::MoniExecuteCommand,
+        notifier::{DesktopNotifier, HookNotifier, Notifier, NotifyOn},
+        presets::Preset,
+        scheduler::ConcurrencyPolicy,
+        workspace::Workspace,
     },
 };
 
@@ -13,12 +25,18 @@ use crate::{
     name = "moni",
     version = "1",
     author = "u-kai",
-    about = "moni is monitaring your file change and execute command!"
+    about = "moni is monitaring your file change and execute command!",
+    subcommand_negates_reqs = true
 )]
 pub struct MoniCli {
-    /// Sets the root monitaring directory
-    #[clap(short, long)]
-    workspace: Option<String>,
+    #[clap(subcommand)]
+    command: Option<MoniCommand>,
+    /// Prints what moni ls prints and exits instead of watching
+    #[clap(long)]
+    dry_run: bool,
+    /// Sets the root monitaring directory, repeat it to watch more roots together
+    #[clap(short, long, parse(from_os_str))]
+    workspace: Vec<PathBuf>,
     /// Sets the some target extensions split by comma or space
     #[clap(short, long)]
     target_extensions: Option<String>,
@@ -28,15 +46,96 @@ pub struct MoniCli {
     /// Sets the some ignore extensions split by comma or space
     #[clap(short = 'n', long)]
     ignore_extensions: Option<String>,
-    /// Sets the some ignore path words split by comma or space
+    /// Matches extensions regardless of case, e.g. jpg and JPG
+    #[clap(long)]
+    case_insensitive_extensions: bool,
+    /// Sets the some ignore path words split by comma or space, regexes of names, or of paths with relative: or absolute:
     #[clap(short = 'p', long)]
     ignore_path_words: Option<String>,
-    /// Sets the execute command
-    #[clap(short, long = "cmd")]
-    execute_command: String,
+    /// Sets the some include path words split by comma or space, only matching files are watched
+    #[clap(long)]
+    include_path_words: Option<String>,
+    /// Watches files in symlinked directories too
+    #[clap(long)]
+    follow_symlinks: bool,
+    /// Sets the depth of directories watched below each root, 0 for files directly in it
+    #[clap(long)]
+    max_depth: Option<usize>,
+    /// Sets the bytes of the largest file watched
+    #[clap(long)]
+    max_file_size: Option<u64>,
+    /// Sets the files watched at most under each root
+    #[clap(long)]
+    max_files: Option<usize>,
+    /// Does not cross mount points
+    #[clap(long)]
+    same_filesystem: bool,
+    /// Sets a file watched too, e.g. ../.env, repeat it to watch more files
+    #[clap(long)]
+    watch_file: Vec<String>,
+    /// Reports created and removed directories, even empty ones
+    #[clap(long)]
+    watch_dirs: bool,
+    /// Sets the directories watched with --watch-dirs, path words split by comma or space
+    #[clap(long)]
+    dir_include_path_words: Option<String>,
+    /// Sets the command executed for created and removed directories
+    #[clap(long = "dir-cmd")]
+    dir_execute_command: Option<String>,
+    /// Does not execute for changed files that look binary, rules on the content are only in moni.json
+    #[clap(long)]
+    skip_binary: bool,
+    /// Sets the execute command, the command of --preset when not set
+    #[clap(short, long = "cmd", required_unless_present_any = &["preset", "dry-run"])]
+    execute_command: Option<String>,
+    /// Adds the usual target extensions, ignore paths and command of: rust, node, python, java, web or docs
+    #[clap(long)]
+    preset: Option<String>,
+    /// Sets the seconds until the execute command is killed
+    #[clap(long)]
+    timeout: Option<f64>,
+    /// Sets what to do with changes while command is running: queue, restart, drop or parallel
+    #[clap(long)]
+    concurrency: Option<String>,
+    /// Sets the max running commands when concurrency is parallel
+    #[clap(long)]
+    max_workers: Option<usize>,
+    /// Executes before watching starts: once or each_file
+    #[clap(long)]
+    run_on_start: Option<String>,
+    /// Sets the file to keep watched files between runs, e.g. .moni/state.json
+    #[clap(long)]
+    snapshot: Option<String>,
+    /// Sets the output format: text, json or tui
+    #[clap(long)]
+    output: Option<String>,
+    /// Sets the file to write the output to in addition to stdout
+    #[clap(long)]
+    log_file: Option<String>,
+    /// Sets when to color the output: auto, always or never
+    #[clap(long)]
+    color: Option<String>,
+    /// Clears the terminal before each execution
+    #[clap(long = "clear")]
+    clear_screen: bool,
+    /// Sets the blank lines printed instead of clearing when the output is not a terminal
+    #[clap(long)]
+    clear_lines: Option<usize>,
+    /// Shows a desktop notification on: always, success or failure
+    #[clap(long)]
+    notify: Option<String>,
+    /// Sets the hook command run on --notify (default failure) with MONI_STATUS, MONI_EXIT_CODE and MONI_COMMAND
+    #[clap(long)]
+    notify_command: Option<String>,
+    /// Sets the name shown as {rule} in messages
+    #[clap(long)]
+    rule: Option<String>,
+    /// Shows a dashboard instead of lines, same as --output tui
+    #[clap(long)]
+    tui: bool,
 }
 
-fn split_space_or_comma<'a>(source: &'a str) -> Vec<&'a str> {
+fn split_space_or_comma(source: &str) -> Vec<&str> {
     if source.contains(",") {
         return source.split(",").collect();
     }
@@ -46,48 +145,166 @@ fn split_space_or_comma<'a>(source: &'a str) -> Vec<&'a str> {
     vec![source]
 }
 impl<'a> MoniConfig<'a, DefaultMoniDebugMessage<'a>> for MoniCli {
-    fn debug_message(&'a self) -> MoniDebuger<DefaultMoniDebugMessage> {
-        MoniDebuger::default()
+    fn debug_message(&'a self) -> MoniDebuger<DefaultMoniDebugMessage<'a>> {
+        match &self.color {
+            Some(mode) => MoniDebuger::default()
+                .color(ColorMode::new(mode).unwrap_or_else(|e| panic!("{}", e))),
+            None => MoniDebuger::default(),
+        }
+    }
+    fn execute_command(&'a self) -> Option<MoniExecuteCommand<'a>> {
+        self.execute_command
+            .as_ref()
+            .map(|command| MoniExecuteCommand::new(command))
     }
-    fn execute_command(&'a self) -> MoniExecuteCommand<'a> {
-        MoniExecuteCommand::new(&self.execute_command)
+    fn preset(&'a self) -> Option<Preset> {
+        self.preset
+            .as_ref()
+            .map(|preset| Preset::new(preset).unwrap_or_else(|e| panic!("{}", e)))
     }
     fn ignore_extensions(&'a self) -> Option<Vec<&'a str>> {
-        if let Some(source) = &self.ignore_extensions {
-            Some(split_space_or_comma(source))
-        } else {
-            None
-        }
+        self.ignore_extensions
+            .as_ref()
+            .map(|source| split_space_or_comma(source))
     }
     fn ignore_filenames(&'a self) -> Option<Vec<&'a str>> {
-        if let Some(source) = &self.ignore_filenames {
-            Some(split_space_or_comma(source))
-        } else {
-            None
-        }
+        self.ignore_filenames
+            .as_ref()
+            .map(|source| split_space_or_comma(source))
     }
     fn ignore_path_words(&'a self) -> Option<Vec<&'a str>> {
-        if let Some(source) = &self.ignore_path_words {
-            Some(split_space_or_comma(source))
+        self.ignore_path_words
+            .as_ref()
+            .map(|source| split_space_or_comma(source))
+    }
+    fn include_path_words(&'a self) -> Option<Vec<&'a str>> {
+        self.include_path_words
+            .as_ref()
+            .map(|source| split_space_or_comma(source))
+    }
+    fn case_insensitive_extensions(&'a self) -> bool {
+        self.case_insensitive_extensions
+    }
+    fn follow_symlinks(&'a self) -> bool {
+        self.follow_symlinks
+    }
+    fn max_depth(&'a self) -> Option<usize> {
+        self.max_depth
+    }
+    fn max_file_size(&'a self) -> Option<u64> {
+        self.max_file_size
+    }
+    fn max_files(&'a self) -> Option<usize> {
+        self.max_files
+    }
+    fn same_filesystem(&'a self) -> bool {
+        self.same_filesystem
+    }
+    fn watch_files(&'a self) -> Vec<&'a str> {
+        self.watch_file.iter().map(|path| path.as_str()).collect()
+    }
+    fn watch_dirs(&'a self) -> bool {
+        self.watch_dirs
+    }
+    fn dir_include_path_words(&'a self) -> Option<Vec<&'a str>> {
+        self.dir_include_path_words
+            .as_ref()
+            .map(|source| split_space_or_comma(source))
+    }
+    fn dir_execute_command(&'a self) -> Option<MoniExecuteCommand<'a>> {
+        self.dir_execute_command
+            .as_ref()
+            .map(|command| MoniExecuteCommand::new(command))
+    }
+    fn content_filters(&'a self) -> Vec<ContentFilter> {
+        if self.skip_binary {
+            vec![ContentFilter::new().skip_binary(true)]
         } else {
-            None
+            Vec::new()
         }
     }
     fn target_extensions(&'a self) -> Option<Vec<&'a str>> {
-        if let Some(source) = &self.target_extensions {
-            Some(split_space_or_comma(source))
-        } else {
-            None
+        self.target_extensions
+            .as_ref()
+            .map(|source| split_space_or_comma(source))
+    }
+    fn workspace(&'a self) -> Option<&'a Path> {
+        self.workspace.first().map(|workspace| workspace.as_path())
+    }
+    /// The roots after the first --workspace.
+    fn workspaces(&'a self) -> Vec<Workspace<'a>> {
+        self.workspace.iter().skip(1).map(Workspace::new).collect()
+    }
+    fn timeout(&'a self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs_f64)
+    }
+    fn concurrency(&'a self) -> Option<ConcurrencyPolicy> {
+        self.concurrency.as_ref().map(|policy| {
+            ConcurrencyPolicy::new(policy, self.max_workers).unwrap_or_else(|e| panic!("{}", e))
+        })
+    }
+    fn output(&'a self) -> Option<MoniOutput> {
+        if self.tui {
+            return Some(MoniOutput::new("tui").unwrap_or_else(|e| panic!("{}", e)));
+        }
+        self.output
+            .as_ref()
+            .map(|output| MoniOutput::new(output).unwrap_or_else(|e| panic!("{}", e)))
+    }
+    fn rule(&'a self) -> Option<&'a str> {
+        self.rule.as_deref()
+    }
+    fn notifiers(&'a self) -> Vec<Box<dyn Notifier>> {
+        let on = match &self.notify {
+            Some(on) => NotifyOn::new(on).unwrap_or_else(|e| panic!("{}", e)),
+            None => NotifyOn::default(),
+        };
+        let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+        if self.notify.is_some() {
+            notifiers.push(Box::new(DesktopNotifier::new(on)));
+        }
+        if let Some(command) = &self.notify_command {
+            notifiers.push(Box::new(HookNotifier::new(on, command)));
         }
+        notifiers
     }
-    fn workspace(&'a self) -> Option<&'a str> {
-        self.workspace.as_ref().map(|s| s.as_str())
+    fn clear_screen(&'a self) -> bool {
+        self.clear_screen
     }
+    fn clear_lines(&'a self) -> Option<usize> {
+        self.clear_lines
+    }
+    fn log_file(&'a self) -> Option<&'a str> {
+        self.log_file.as_deref()
+    }
+    fn snapshot(&'a self) -> Option<&'a str> {
+        self.snapshot.as_deref()
+    }
+    fn run_on_start(&'a self) -> Option<RunOnStart> {
+        self.run_on_start
+            .as_ref()
+            .map(|run_on_start| RunOnStart::new(run_on_start).unwrap_or_else(|e| panic!("{}", e)))
+    }
+}
+/// Ways to check the filters without watching.
+#[derive(Subcommand, Debug)]
+pub enum MoniCommand {
+    /// Prints the watched files and the rule that excluded each of the others
+    Ls,
+    /// Prints why <PATH> is watched or not
+    Explain { path: String },
 }
+
 impl MoniCli {
     pub fn monitaring(&self) {
-        let moni = self.to_moni();
-        moni.monitaring()
+        match &self.command {
+            Some(MoniCommand::Ls) => print!("{}", self.to_moni_builder().ls()),
+            Some(MoniCommand::Explain { path }) => {
+                print!("{}", self.to_moni_builder().explain(Path::new(path)))
+            }
+            None if self.dry_run => print!("{}", self.to_moni_builder().ls()),
+            None => self.to_moni().monitaring(),
+        }
     }
 }
 
@@ -104,16 +321,56 @@ mod test_moni_cli_config {
             execute_command: &str,
         ) -> Self {
             Self {
-                workspace: Some(workspace.to_string()),
+                command: None,
+                dry_run: false,
+                workspace: vec![PathBuf::from(workspace)],
                 target_extensions: Some(target_extensions.to_string()),
                 ignore_filenames: Some(ignore_filenames.to_string()),
                 ignore_extensions: Some(ignore_extensions.to_string()),
+                case_insensitive_extensions: false,
                 ignore_path_words: Some(ignore_path_words.to_string()),
-                execute_command: execute_command.to_string(),
+                include_path_words: None,
+                follow_symlinks: false,
+                max_depth: None,
+                max_file_size: None,
+                max_files: None,
+                same_filesystem: false,
+                watch_file: Vec::new(),
+                watch_dirs: false,
+                dir_include_path_words: None,
+                dir_execute_command: None,
+                skip_binary: false,
+                execute_command: Some(execute_command.to_string()),
+                preset: None,
+                timeout: None,
+                concurrency: None,
+                max_workers: None,
+                run_on_start: None,
+                snapshot: None,
+                output: None,
+                log_file: None,
+                color: None,
+                clear_screen: false,
+                clear_lines: None,
+                notify: None,
+                notify_command: None,
+                rule: None,
+                tui: false,
             }
         }
     }
     #[test]
+    fn test_parse_case_without_cmd() {
+        assert!(MoniCli::try_parse_from(["moni"]).is_err());
+        assert!(MoniCli::try_parse_from(["moni", "--dry-run"]).is_ok());
+        let moni_cli =
+            MoniCli::try_parse_from(["moni", "-w", "./tests", "explain", "a.rs"]).unwrap();
+        assert!(matches!(
+            moni_cli.command,
+            Some(MoniCommand::Explain { path }) if path == "a.rs"
+        ));
+    }
+    #[test]
     fn test_ignore_extensions_case_split_space() {
         let moni_cli = MoniCli::new("test", "py js", "", "", "", "");
         assert_eq!(moni_cli.ignore_extensions().unwrap(), vec!["py", "js"]);
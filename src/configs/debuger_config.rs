@@ -1,7 +1,64 @@
+use crate::parts::color::{ColorMode, MoniTheme};
+
 pub trait MoniDebugerConfig {
     fn start_message(&self) -> String;
     fn success_message(&self) -> String;
     fn error_message(&self) -> String;
+    fn timeout_message(&self) -> String {
+        " timeout ".to_string()
+    }
     fn execute_message(&self, command: &str) -> String;
+    /// e.g. `schedule` is `queue` or `drop`
+    fn schedule_message(&self, schedule: &str, command: &str) -> String {
+        format!(" {} {} ", schedule, command)
+    }
     fn line_message(&self) -> String;
+    /// e.g. `elapsed` is `1.23s`, `exit_code` is None for exe_fn and killed commands
+    fn finished_message(&self, elapsed: &str, exit_code: Option<i32>) -> String {
+        match exit_code {
+            Some(exit_code) => format!(" {} exit {} ", elapsed, exit_code),
+            None => format!(" {} ", elapsed),
+        }
+    }
+    fn theme(&self) -> MoniTheme {
+        MoniTheme::default()
+    }
+    fn color_mode(&self) -> ColorMode {
+        ColorMode::default()
+    }
+}
+
+#[cfg(test)]
+mod test_debuger_config {
+    use super::*;
+    struct Messages;
+    impl MoniDebugerConfig for Messages {
+        fn start_message(&self) -> String {
+            " start ".to_string()
+        }
+        fn success_message(&self) -> String {
+            " success ".to_string()
+        }
+        fn error_message(&self) -> String {
+            " error ".to_string()
+        }
+        fn execute_message(&self, command: &str) -> String {
+            format!(" execute {} ", command)
+        }
+        fn line_message(&self) -> String {
+            " --- ".to_string()
+        }
+    }
+    #[test]
+    fn test_default_messages() {
+        assert_eq!(Messages.timeout_message(), " timeout ");
+        assert_eq!(
+            Messages.schedule_message("queue", "cargo test"),
+            " queue cargo test "
+        );
+        assert_eq!(
+            Messages.finished_message("1.23s", Some(1)),
+            " 1.23s exit 1 "
+        );
+    }
 }
@@ -3,25 +3,189 @@ use std::{
     fs::File,
     io::{BufReader, Read},
     path::Path,
+    time::Duration,
 };
 
 use serde::{Deserialize, Serialize};
 
-use crate::parts::{
-    debuger::{DefaultMoniDebugMessage, MoniDebuger},
-    moni_execute_command::MoniExecuteCommand,
+use crate::{
+    moni::RunOnStart,
+    parts::{
+        color::{Color, ColorMode, MoniTheme},
+        content_filter::ContentFilter,
+        debuger::{DefaultMoniDebugMessage, MoniDebuger, MoniOutput},
+        moni_execute_command::MoniExecuteCommand,
+        notifier::{DesktopNotifier, HookNotifier, Notifier, NotifyOn},
+        presets::Preset,
+        scheduler::ConcurrencyPolicy,
+        workspace::Workspace,
+    },
 };
 
 use super::{debuger_config::MoniDebugerConfig, moni_config::MoniConfig};
 #[derive(Serialize, Deserialize)]
 pub struct MoniJson {
     workspace: Option<String>,
+    /// more roots, each a path or an object with its own filters
+    workspaces: Option<Vec<MoniWorkspaceJson>>,
     target_extensions: Option<Vec<String>>,
     ignore_filenames: Option<Vec<String>>,
     ignore_extensions: Option<Vec<String>>,
+    /// match extensions regardless of case, e.g. jpg and JPG
+    case_insensitive_extensions: Option<bool>,
+    /// regexes of names, or of paths with relative: or absolute:
     ignore_path_words: Option<Vec<String>>,
+    /// watch only files matching one of these, like ignore_path_words
+    include_path_words: Option<Vec<String>>,
+    /// walk into symlinked directories too
+    follow_symlinks: Option<bool>,
+    /// directories deeper than this below each root are not watched
+    max_depth: Option<usize>,
+    /// bytes, larger files are not watched
+    max_file_size: Option<u64>,
+    /// files watched at most under each root
+    max_files: Option<usize>,
+    /// do not cross mount points
+    same_filesystem: Option<bool>,
+    /// files watched too, e.g. ../.env, which may not exist yet
+    watch_files: Option<Vec<String>>,
+    /// report created and removed directories, even empty ones
+    watch_dirs: Option<bool>,
+    /// watch only directories matching one of these, like ignore_path_words
+    dir_include_path_words: Option<Vec<String>>,
+    /// executed for created and removed directories, the files command is not
+    dir_execute_command: Option<String>,
+    /// rules on the content of changed files, all applied ones must pass
+    content_filters: Option<Vec<MoniContentFilterJson>>,
     debug_message: Option<MoniDebugerConfigJson>,
-    execute_command: String,
+    /// the command of preset when not set
+    execute_command: Option<String>,
+    /// rust, node, python, java, web or docs
+    preset: Option<String>,
+    /// seconds until the execute command is killed
+    timeout: Option<f64>,
+    /// queue, restart, drop or parallel
+    concurrency: Option<String>,
+    /// max running commands when concurrency is parallel
+    max_workers: Option<usize>,
+    /// once or each_file
+    run_on_start: Option<String>,
+    /// file to keep watched files between runs, e.g. .moni/state.json
+    snapshot: Option<String>,
+    /// text or json
+    output: Option<String>,
+    /// file to write the output to in addition to stdout
+    log_file: Option<String>,
+    /// clear the terminal before each execution
+    clear_screen: Option<bool>,
+    /// blank lines instead of clearing when the output is not a terminal
+    clear_lines: Option<usize>,
+    notify: Option<MoniNotifyJson>,
+    /// name shown as {rule} in debug messages
+    rule: Option<String>,
+}
+
+/// e.g. "../shared-proto" or {"path": "../shared-proto", "target_extensions": ["proto"]}
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum MoniWorkspaceJson {
+    Path(String),
+    Filtered {
+        path: String,
+        target_extensions: Option<Vec<String>>,
+        ignore_filenames: Option<Vec<String>>,
+        ignore_extensions: Option<Vec<String>>,
+        ignore_path_words: Option<Vec<String>>,
+        include_path_words: Option<Vec<String>>,
+    },
+}
+impl MoniWorkspaceJson {
+    fn to_workspace(&self) -> Workspace<'_> {
+        match self {
+            Self::Path(path) => Workspace::new(path),
+            Self::Filtered {
+                path,
+                target_extensions,
+                ignore_filenames,
+                ignore_extensions,
+                ignore_path_words,
+                include_path_words,
+            } => {
+                let mut workspace = Workspace::new(path);
+                if let Some(extensions) = opt_string_vec_to_str_vec(target_extensions.as_ref()) {
+                    workspace = workspace.target_extensions(extensions);
+                }
+                if let Some(filenames) = opt_string_vec_to_str_vec(ignore_filenames.as_ref()) {
+                    workspace = workspace.ignore_filenames(filenames);
+                }
+                if let Some(extensions) = opt_string_vec_to_str_vec(ignore_extensions.as_ref()) {
+                    workspace = workspace.ignore_extensions(extensions);
+                }
+                if let Some(re) = opt_string_vec_to_str_vec(ignore_path_words.as_ref()) {
+                    workspace = workspace.ignore_path_words(re);
+                }
+                if let Some(re) = opt_string_vec_to_str_vec(include_path_words.as_ref()) {
+                    workspace = workspace.include_path_words(re);
+                }
+                workspace
+            }
+        }
+    }
+}
+
+/// e.g. {"path": "relative:^src/generated/", "ignore_lines": "^// generated at"}
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MoniContentFilterJson {
+    /// files the rule applies to, like ignore_path_words, every file when not set
+    path: Option<String>,
+    /// changes of only these lines do not trigger
+    ignore_lines: Option<String>,
+    /// trigger only when the content contains this
+    marker: Option<String>,
+    /// files with a NUL byte near the start do not trigger
+    skip_binary: Option<bool>,
+}
+impl MoniContentFilterJson {
+    fn to_content_filter(&self) -> ContentFilter {
+        let mut content_filter = ContentFilter::new();
+        if let Some(path) = &self.path {
+            content_filter = content_filter.path(path);
+        }
+        if let Some(ignore_lines) = &self.ignore_lines {
+            content_filter = content_filter.ignore_lines(ignore_lines);
+        }
+        if let Some(marker) = &self.marker {
+            content_filter = content_filter.marker(marker);
+        }
+        content_filter.skip_binary(self.skip_binary.unwrap_or(false))
+    }
+}
+
+/// e.g. {"on": "failure", "desktop": true, "command": "curl -d \"$MONI_STATUS\" ..."}
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MoniNotifyJson {
+    /// always, success or failure
+    on: Option<String>,
+    /// desktop notification, true unless only command is set
+    desktop: Option<bool>,
+    /// hook command
+    command: Option<String>,
+}
+impl MoniNotifyJson {
+    fn to_notifiers(&self) -> Vec<Box<dyn Notifier>> {
+        let on = match &self.on {
+            Some(on) => NotifyOn::new(on).unwrap_or_else(|e| panic!("{}", e)),
+            None => NotifyOn::default(),
+        };
+        let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+        if self.desktop.unwrap_or(self.command.is_none()) {
+            notifiers.push(Box::new(DesktopNotifier::new(on)));
+        }
+        if let Some(command) = &self.command {
+            notifiers.push(Box::new(HookNotifier::new(on, command)));
+        }
+        notifiers
+    }
 }
 impl MoniJson {
     pub fn from_file<P: AsRef<Path> + Debug>(filepath: P) -> Result<Self, String> {
@@ -40,8 +204,15 @@ impl MoniJson {
     }
 }
 impl<'a> MoniConfig<'a, MoniDebugerConfigJson> for MoniJson {
-    fn execute_command(&'a self) -> MoniExecuteCommand<'a> {
-        MoniExecuteCommand::new(&self.execute_command)
+    fn execute_command(&'a self) -> Option<MoniExecuteCommand<'a>> {
+        self.execute_command
+            .as_ref()
+            .map(|command| MoniExecuteCommand::new(command))
+    }
+    fn preset(&'a self) -> Option<Preset> {
+        self.preset
+            .as_ref()
+            .map(|preset| Preset::new(preset).unwrap_or_else(|e| panic!("{}", e)))
     }
     fn ignore_extensions(&'a self) -> Option<Vec<&'a str>> {
         opt_string_vec_to_str_vec(self.ignore_extensions.as_ref())
@@ -52,11 +223,99 @@ impl<'a> MoniConfig<'a, MoniDebugerConfigJson> for MoniJson {
     fn ignore_path_words(&'a self) -> Option<Vec<&'a str>> {
         opt_string_vec_to_str_vec(self.ignore_path_words.as_ref())
     }
+    fn include_path_words(&'a self) -> Option<Vec<&'a str>> {
+        opt_string_vec_to_str_vec(self.include_path_words.as_ref())
+    }
+    fn case_insensitive_extensions(&'a self) -> bool {
+        self.case_insensitive_extensions.unwrap_or(false)
+    }
+    fn follow_symlinks(&'a self) -> bool {
+        self.follow_symlinks.unwrap_or(false)
+    }
+    fn max_depth(&'a self) -> Option<usize> {
+        self.max_depth
+    }
+    fn max_file_size(&'a self) -> Option<u64> {
+        self.max_file_size
+    }
+    fn max_files(&'a self) -> Option<usize> {
+        self.max_files
+    }
+    fn same_filesystem(&'a self) -> bool {
+        self.same_filesystem.unwrap_or(false)
+    }
+    fn watch_files(&'a self) -> Vec<&'a str> {
+        opt_string_vec_to_str_vec(self.watch_files.as_ref()).unwrap_or_default()
+    }
+    fn watch_dirs(&'a self) -> bool {
+        self.watch_dirs.unwrap_or(false)
+    }
+    fn dir_include_path_words(&'a self) -> Option<Vec<&'a str>> {
+        opt_string_vec_to_str_vec(self.dir_include_path_words.as_ref())
+    }
+    fn dir_execute_command(&'a self) -> Option<MoniExecuteCommand<'a>> {
+        self.dir_execute_command
+            .as_ref()
+            .map(|command| MoniExecuteCommand::new(command))
+    }
+    fn content_filters(&'a self) -> Vec<ContentFilter> {
+        self.content_filters
+            .iter()
+            .flatten()
+            .map(|content_filter| content_filter.to_content_filter())
+            .collect()
+    }
     fn target_extensions(&'a self) -> Option<Vec<&'a str>> {
         opt_string_vec_to_str_vec(self.target_extensions.as_ref())
     }
-    fn workspace(&'a self) -> Option<&'a str> {
-        self.workspace.as_ref().map(|s| s.as_str())
+    fn workspace(&'a self) -> Option<&'a Path> {
+        self.workspace.as_deref().map(Path::new)
+    }
+    fn workspaces(&'a self) -> Vec<Workspace<'a>> {
+        self.workspaces
+            .iter()
+            .flatten()
+            .map(|workspace| workspace.to_workspace())
+            .collect()
+    }
+    fn timeout(&'a self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs_f64)
+    }
+    fn concurrency(&'a self) -> Option<ConcurrencyPolicy> {
+        self.concurrency.as_ref().map(|policy| {
+            ConcurrencyPolicy::new(policy, self.max_workers).unwrap_or_else(|e| panic!("{}", e))
+        })
+    }
+    fn output(&'a self) -> Option<MoniOutput> {
+        self.output
+            .as_ref()
+            .map(|output| MoniOutput::new(output).unwrap_or_else(|e| panic!("{}", e)))
+    }
+    fn rule(&'a self) -> Option<&'a str> {
+        self.rule.as_deref()
+    }
+    fn notifiers(&'a self) -> Vec<Box<dyn Notifier>> {
+        self.notify
+            .as_ref()
+            .map(|notify| notify.to_notifiers())
+            .unwrap_or_default()
+    }
+    fn clear_screen(&'a self) -> bool {
+        self.clear_screen.unwrap_or(false)
+    }
+    fn clear_lines(&'a self) -> Option<usize> {
+        self.clear_lines
+    }
+    fn log_file(&'a self) -> Option<&'a str> {
+        self.log_file.as_deref()
+    }
+    fn snapshot(&'a self) -> Option<&'a str> {
+        self.snapshot.as_deref()
+    }
+    fn run_on_start(&'a self) -> Option<RunOnStart> {
+        self.run_on_start
+            .as_ref()
+            .map(|run_on_start| RunOnStart::new(run_on_start).unwrap_or_else(|e| panic!("{}", e)))
     }
     fn debug_message(&'a self) -> MoniDebuger<MoniDebugerConfigJson> {
         if let Some(config) = self.debug_message.as_ref() {
@@ -68,24 +327,61 @@ impl<'a> MoniConfig<'a, MoniDebugerConfigJson> for MoniJson {
     }
 }
 
-fn string_vec_to_str_vec<'a>(string_vec: &'a [String]) -> Vec<&'a str> {
+fn string_vec_to_str_vec(string_vec: &[String]) -> Vec<&str> {
     string_vec.iter().map(|s| s.as_str()).collect()
 }
-fn opt_string_vec_to_str_vec<'a>(string_vec: Option<&'a Vec<String>>) -> Option<Vec<&'a str>> {
-    if let Some(string_vec) = string_vec {
-        Some(string_vec_to_str_vec(string_vec))
-    } else {
-        None
-    }
+fn opt_string_vec_to_str_vec(string_vec: Option<&Vec<String>>) -> Option<Vec<&str>> {
+    string_vec.map(|string_vec| string_vec_to_str_vec(string_vec))
 }
+/// Every message is a template of {command}, {file}, {exit_code}, {duration}, {time},
+/// {run_number} and {rule}. MONI_EXE and MONI_SCHEDULE are still replaced too.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct MoniDebugerConfigJson {
     title: Option<String>,
     success: Option<String>,
     error: Option<String>,
+    timeout: Option<String>,
+    line: Option<String>,
+    execute: Option<String>,
+    /// {schedule} is queue, drop or restart
+    schedule: Option<String>,
+    /// line after the output of each run
+    finished: Option<String>,
+    /// auto, always or never
+    color: Option<String>,
+    colors: Option<MoniThemeJson>,
+}
+
+/// Color names of each line, e.g. {"success": "green", "line": "dim"}
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct MoniThemeJson {
+    title: Option<String>,
+    success: Option<String>,
+    error: Option<String>,
+    timeout: Option<String>,
+    warning: Option<String>,
     line: Option<String>,
-    //$COMMAND
     execute: Option<String>,
+    schedule: Option<String>,
+}
+impl MoniThemeJson {
+    fn to_theme(&self) -> MoniTheme {
+        let default = MoniTheme::default();
+        let color = |name: &Option<String>, default: Color| match name {
+            Some(name) => Color::new(name).unwrap_or_else(|e| panic!("{}", e)),
+            None => default,
+        };
+        MoniTheme {
+            start: color(&self.title, default.start),
+            success: color(&self.success, default.success),
+            error: color(&self.error, default.error),
+            timeout: color(&self.timeout, default.timeout),
+            warning: color(&self.warning, default.warning),
+            execute: color(&self.execute, default.execute),
+            schedule: color(&self.schedule, default.schedule),
+            line: color(&self.line, default.line),
+        }
+    }
 }
 impl<'a> From<DefaultMoniDebugMessage<'a>> for MoniDebugerConfigJson {
     fn from(message: DefaultMoniDebugMessage<'a>) -> Self {
@@ -93,15 +389,30 @@ impl<'a> From<DefaultMoniDebugMessage<'a>> for MoniDebugerConfigJson {
             title: Some(message.start_message()),
             success: Some(message.success_message()),
             error: Some(message.error_message()),
+            timeout: Some(message.timeout_message()),
             line: Some(message.line_message()),
-            execute: Some(format!(" execute {}", Self::MONI_EXECUTE_COMMAND_MARK)),
+            execute: Some(" execute {command}".to_string()),
+            schedule: Some(" {schedule} {command}".to_string()),
+            finished: None,
+            color: None,
+            colors: None,
         }
     }
 }
 impl MoniDebugerConfigJson {
     const MONI_EXECUTE_COMMAND_MARK: &'static str = "MONI_EXE";
+    const MONI_SCHEDULE_MARK: &'static str = "MONI_SCHEDULE";
 }
 impl MoniDebugerConfig for MoniDebugerConfigJson {
+    fn theme(&self) -> MoniTheme {
+        self.colors.clone().unwrap_or_default().to_theme()
+    }
+    fn color_mode(&self) -> ColorMode {
+        match &self.color {
+            Some(mode) => ColorMode::new(mode).unwrap_or_else(|e| panic!("{}", e)),
+            None => ColorMode::default(),
+        }
+    }
     fn error_message(&self) -> String {
         if let Some(error) = &self.error {
             error.to_owned()
@@ -109,16 +420,35 @@ impl MoniDebugerConfig for MoniDebugerConfigJson {
             " error ".to_string()
         }
     }
-    fn execute_message(&self, execute_command: &str) -> String {
-        let line = self.line_message().replace("-", "*");
-        if let Some(execute) = &self.execute {
-            format!(
-                "{}\n{}",
-                line,
-                execute.replace(Self::MONI_EXECUTE_COMMAND_MARK, execute_command)
-            )
+    fn timeout_message(&self) -> String {
+        if let Some(timeout) = &self.timeout {
+            timeout.to_owned()
         } else {
-            format!("{}\n{}", line, " execute ".to_string())
+            " timeout ".to_string()
+        }
+    }
+    fn execute_message(&self, execute_command: &str) -> String {
+        let execute = self.execute.as_deref().unwrap_or(" execute {command} ");
+        // the command of a queued or dropped line is not the running one, so replace it here
+        execute
+            .replace(Self::MONI_EXECUTE_COMMAND_MARK, "{command}")
+            .replace("{command}", execute_command)
+    }
+    fn schedule_message(&self, schedule: &str, execute_command: &str) -> String {
+        let message = self.schedule.as_deref().unwrap_or(" {schedule} {command} ");
+        message
+            .replace(Self::MONI_SCHEDULE_MARK, "{schedule}")
+            .replace(Self::MONI_EXECUTE_COMMAND_MARK, "{command}")
+            .replace("{schedule}", schedule)
+            .replace("{command}", execute_command)
+    }
+    fn finished_message(&self, elapsed: &str, exit_code: Option<i32>) -> String {
+        match &self.finished {
+            Some(finished) => finished.to_owned(),
+            None => match exit_code {
+                Some(exit_code) => format!(" {} exit {} ", elapsed, exit_code),
+                None => format!(" {} ", elapsed),
+            },
         }
     }
     fn line_message(&self) -> String {
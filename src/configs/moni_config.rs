@@ -1,38 +1,153 @@
+use std::{path::Path, time::Duration};
+
 use crate::{
-    moni::{Moni, MoniBuilder},
-    parts::{debuger::MoniDebuger, moni_execute_command::MoniExecuteCommand},
+    moni::{Moni, MoniBuilder, RunOnStart},
+    parts::{
+        content_filter::ContentFilter,
+        debuger::{MoniDebuger, MoniOutput},
+        moni_execute_command::MoniExecuteCommand,
+        notifier::Notifier,
+        presets::Preset,
+        scheduler::ConcurrencyPolicy,
+        workspace::Workspace,
+    },
 };
 
 use super::debuger_config::MoniDebugerConfig;
 
 pub trait MoniConfig<'a, D: MoniDebugerConfig> {
-    fn workspace(&'a self) -> Option<&'a str>;
+    fn workspace(&'a self) -> Option<&'a Path>;
+    /// More roots watched with workspace in one scan.
+    fn workspaces(&'a self) -> Vec<Workspace<'a>>;
     fn ignore_filenames(&'a self) -> Option<Vec<&'a str>>;
     fn ignore_extensions(&'a self) -> Option<Vec<&'a str>>;
     fn ignore_path_words(&'a self) -> Option<Vec<&'a str>>;
+    fn include_path_words(&'a self) -> Option<Vec<&'a str>>;
     fn target_extensions(&'a self) -> Option<Vec<&'a str>>;
-    fn execute_command(&'a self) -> MoniExecuteCommand<'a>;
+    fn case_insensitive_extensions(&'a self) -> bool;
+    fn follow_symlinks(&'a self) -> bool;
+    fn max_depth(&'a self) -> Option<usize>;
+    fn max_file_size(&'a self) -> Option<u64>;
+    fn max_files(&'a self) -> Option<usize>;
+    fn same_filesystem(&'a self) -> bool;
+    /// Files watched in addition to the workspaces.
+    fn watch_files(&'a self) -> Vec<&'a str>;
+    fn watch_dirs(&'a self) -> bool;
+    fn dir_include_path_words(&'a self) -> Option<Vec<&'a str>>;
+    /// Executed for created and removed directories.
+    fn dir_execute_command(&'a self) -> Option<MoniExecuteCommand<'a>>;
+    /// Checked in order on each created or modified file.
+    fn content_filters(&'a self) -> Vec<ContentFilter>;
+    fn execute_command(&'a self) -> Option<MoniExecuteCommand<'a>>;
+    fn preset(&'a self) -> Option<Preset>;
+    fn timeout(&'a self) -> Option<Duration>;
+    fn concurrency(&'a self) -> Option<ConcurrencyPolicy>;
+    fn run_on_start(&'a self) -> Option<RunOnStart>;
+    fn snapshot(&'a self) -> Option<&'a str>;
+    fn output(&'a self) -> Option<MoniOutput>;
+    fn log_file(&'a self) -> Option<&'a str>;
+    fn clear_screen(&'a self) -> bool;
+    fn clear_lines(&'a self) -> Option<usize>;
+    fn rule(&'a self) -> Option<&'a str>;
+    fn notifiers(&'a self) -> Vec<Box<dyn Notifier>>;
     fn debug_message(&'a self) -> MoniDebuger<D>;
     fn to_moni(&'a self) -> Moni<'a, D> {
         let debuger = self.debug_message();
         self.to_moni_with_debuger(debuger)
     }
-    fn to_moni_with_debuger(&'a self, debuger: MoniDebuger<D>) -> Moni<'a, D> {
-        let mut builder = MoniBuilder::new().exe_command(self.execute_command());
+    fn to_moni_with_debuger(&'a self, mut debuger: MoniDebuger<D>) -> Moni<'a, D> {
+        if self.output().is_some() {
+            debuger.set_output(self.output().unwrap());
+        }
+        debuger.set_clear_screen(self.clear_screen());
+        if self.clear_lines().is_some() {
+            debuger.set_clear_lines(self.clear_lines().unwrap());
+        }
+        if self.rule().is_some() {
+            debuger.set_rule(self.rule().unwrap());
+        }
+        if self.execute_command().is_none() && self.preset().is_none() {
+            panic!("execute_command or preset must be set")
+        }
+        self.to_moni_builder().build_with_debuger(debuger)
+    }
+    /// The builder of `to_moni`, which `ls` and `explain` need without a command.
+    fn to_moni_builder(&'a self) -> MoniBuilder<'a> {
+        let mut builder = MoniBuilder::new();
+        if self.execute_command().is_some() {
+            builder.set_exe_command(self.execute_command().unwrap());
+        }
         if self.ignore_filenames().is_some() {
             builder.set_ignore_files(self.ignore_filenames().unwrap())
         }
         if self.ignore_path_words().is_some() {
             builder.set_ignore_re(self.ignore_path_words().unwrap())
         }
+        if self.include_path_words().is_some() {
+            builder.set_include_re(self.include_path_words().unwrap())
+        }
         if self.target_extensions().is_some() {
             builder.set_target_extensions(self.target_extensions().unwrap())
         }
-        if self.workspace().is_some() {
-            builder.set_root(self.workspace().unwrap());
+        if self.ignore_extensions().is_some() {
+            builder.set_ignore_extensions(self.ignore_extensions().unwrap())
+        }
+        // after the lists above, which replace instead of add
+        if self.preset().is_some() {
+            builder.set_preset(self.preset().unwrap());
+        }
+        builder.set_case_insensitive_extensions(self.case_insensitive_extensions());
+        builder.set_follow_symlinks(self.follow_symlinks());
+        if self.max_depth().is_some() {
+            builder.set_max_depth(self.max_depth().unwrap());
+        }
+        if self.max_file_size().is_some() {
+            builder.set_max_file_size(self.max_file_size().unwrap());
+        }
+        if self.max_files().is_some() {
+            builder.set_max_files(self.max_files().unwrap());
+        }
+        builder.set_same_filesystem(self.same_filesystem());
+        builder.set_watch_files(self.watch_files());
+        builder.set_watch_dirs(self.watch_dirs());
+        if self.dir_include_path_words().is_some() {
+            builder.set_dir_include_re(self.dir_include_path_words().unwrap());
+        }
+        if self.dir_execute_command().is_some() {
+            builder.set_dir_exe_command(self.dir_execute_command().unwrap());
+        }
+        for content_filter in self.content_filters() {
+            builder.add_content_filter(content_filter);
+        }
+        if self.timeout().is_some() {
+            builder.set_timeout(self.timeout().unwrap());
+        }
+        if self.concurrency().is_some() {
+            builder.set_concurrency(self.concurrency().unwrap());
+        }
+        if self.run_on_start().is_some() {
+            builder.set_run_on_start(self.run_on_start().unwrap());
+        }
+        if self.snapshot().is_some() {
+            builder.set_snapshot(self.snapshot().unwrap());
+        }
+        if self.log_file().is_some() {
+            builder.set_log_file(self.log_file().unwrap());
+        }
+        for notifier in self.notifiers() {
+            builder.add_notifier(notifier);
+        }
+        let workspaces = self.workspaces();
+        if workspaces.is_empty() {
+            builder.set_root(self.workspace().unwrap_or(Path::new("./")));
         } else {
-            builder.set_root("./");
+            if self.workspace().is_some() {
+                builder.add_workspace(Workspace::new(self.workspace().unwrap()));
+            }
+            for workspace in workspaces {
+                builder.add_workspace(workspace);
+            }
         }
-        builder.build_with_debuger(debuger)
+        builder
     }
 }
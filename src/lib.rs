@@ -1,9 +1,23 @@
 pub mod parts {
+    pub mod color;
+    pub mod content_filter;
+    pub mod control;
+    pub mod dashboard;
     pub mod debuger;
+    pub mod executor;
     pub mod extensions;
     pub mod filesearcher;
     pub mod filestore;
+    pub mod listing;
     pub mod moni_execute_command;
+    pub mod notifier;
+    pub mod pattern;
+    pub mod presets;
+    pub mod scheduler;
+    pub mod sink;
+    pub mod stats;
+    pub mod template;
+    pub mod workspace;
 }
 pub mod cli;
 pub mod moni;
@@ -2,10 +2,13 @@ use core::time;
 #[cfg(not(target_os = "windows"))]
 use std::os::unix::prelude::MetadataExt;
 use std::{
-    fs::{File, Metadata},
-    process::{Command, Stdio},
-    sync::{Arc, Mutex},
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fs::{self, File, Metadata},
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
     thread,
+    time::{Duration, Instant},
 };
 
 use crate::configs::{debuger_config::MoniDebugerConfig, json::MoniDebugerConfigJson};
@@ -13,14 +16,24 @@ use crate::configs::{debuger_config::MoniDebugerConfig, json::MoniDebugerConfigJ
 use super::{
     configs::{json::MoniJson, moni_config::MoniConfig},
     parts::{
+        content_filter::{ContentFilter, ContentFilters},
+        control::{ControlEvent, MoniControl},
         debuger::{DefaultMoniDebugMessage, MoniDebuger},
+        executor::{ExecuteStatus, ExecutionResult},
         filesearcher::{FileSearcher, FileSearcherBuilder},
-        filestore::FileStore,
+        filestore::{FileEvent, FileStore, SnapshotRoots},
+        listing,
         moni_execute_command::MoniExecuteCommand,
+        notifier::Notifier,
+        presets::Preset,
+        scheduler::{ConcurrencyPolicy, MoniScheduler, RunId, ScheduleEvent},
+        sink::{FileSink, MoniSink, StdoutSink, TeeSink},
+        stats::MoniStats,
+        workspace::{find_root, Workspace},
     },
 };
 
-pub fn monitaring_from_json() -> () {
+pub fn monitaring_from_json() {
     let json = MoniJson::from_file("moni.json").unwrap();
     if json.is_set_debug_message() {
         json.to_moni().monitaring();
@@ -31,114 +44,355 @@ pub fn monitaring_from_json() -> () {
         json.to_moni_with_debuger(debuger).monitaring()
     }
 }
-type CallBack = Box<dyn Fn(&str) -> Result<String, String>>;
+/// Prints what moni.json would watch, like `moni ls`.
+pub fn ls_from_json() {
+    let json = MoniJson::from_file("moni.json").unwrap();
+    print!("{}", json.to_moni_builder().ls());
+}
+/// Prints why moni.json would watch `path` or not, like `moni explain`.
+pub fn explain_from_json(path: &str) {
+    let json = MoniJson::from_file("moni.json").unwrap();
+    print!("{}", json.to_moni_builder().explain(Path::new(path)));
+}
+type CallBack = Box<dyn Fn(&Path) -> Result<String, String>>;
+
+/// How to execute before watching starts, so the first output does not wait for an edit.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RunOnStart {
+    /// execute once with the workspace root as MONI_FILE_PATH, and once with each
+    /// watch file outside the workspaces
+    Once,
+    /// execute for every watched file, watch files included
+    EachFile,
+}
+impl RunOnStart {
+    pub fn new(run_on_start: &str) -> Result<Self, String> {
+        match run_on_start {
+            "once" => Ok(Self::Once),
+            "each_file" => Ok(Self::EachFile),
+            _ => Err(format!(
+                "{} is not run_on_start. use once or each_file",
+                run_on_start
+            )),
+        }
+    }
+}
 
 pub struct Moni<'a, D: MoniDebugerConfig> {
     exe_command: Option<MoniExecuteCommand<'a>>,
+    /// executed for created and removed directories instead of exe_command
+    dir_exe_command: Option<MoniExecuteCommand<'a>>,
     exe_fn: Option<CallBack>,
     filestore: Arc<Mutex<FileStore>>,
-    searcher: FileSearcher<'a>,
+    /// one for each workspace
+    searchers: Vec<FileSearcher<'a>>,
+    /// files watched in addition to the workspaces, which may not exist yet
+    watch_files: Vec<&'a Path>,
     around_secs: u64,
     around_nanos: u32,
+    scheduler: RefCell<MoniScheduler>,
+    run_on_start: Option<RunOnStart>,
+    snapshot: Option<&'a Path>,
+    /// so the snapshot keys do not depend on how the roots are spelled
+    snapshot_roots: SnapshotRoots,
+    log_file: Option<&'a Path>,
+    notifiers: Vec<Box<dyn Notifier>>,
+    stats: RefCell<MoniStats>,
+    /// the changed file of each requested command, for `{file}` once it starts
+    command_files: RefCell<HashMap<RunId, String>>,
+    last_file: RefCell<Option<PathBuf>>,
+    paused: Cell<bool>,
+    /// warnings of the last scan, printed when they first show up
+    scan_warnings: RefCell<Vec<String>>,
+    content_filters: RefCell<ContentFilters>,
     debuger: MoniDebuger<D>,
 }
 impl<'a, C: MoniConfig<'a, D>, D: MoniDebugerConfig> From<&'a C> for Moni<'a, D> {
     fn from(config: &'a C) -> Self {
-        let c = config.to_moni();
-        c
+        config.to_moni()
     }
 }
 
 impl<'a, D: MoniDebugerConfig> Moni<'a, D> {
+    /// Watches until Ctrl-C or `q`, then prints the session summary.
     pub fn monitaring(&self) {
+        let control = MoniControl::listen();
         self.debuger.print_start_line();
+        self.run_on_start();
+        self.save_snapshot();
         loop {
             thread::sleep(time::Duration::new(self.around_secs, self.around_nanos));
-            self.searcher
-                .get_all_filenames()
-                .into_iter()
-                .filter_map(|filepath| {
-                    let file_path_str = filepath.as_str();
-                    match get_file_size(file_path_str) {
-                        Some(size) => Some((filepath, size)),
-                        None => None,
+            self.debuger.refresh();
+            if !self.handle_control_events(control.poll()) {
+                return;
+            }
+            let events = self.scheduler.borrow_mut().poll();
+            self.handle_schedule_events(events);
+            self.scan_changes();
+        }
+    }
+    /// Takes the changes since the last scan into the store and executes for them.
+    fn scan_changes(&self) {
+        let (current, warnings) = scan_filestore(&self.searchers, &self.watch_files);
+        self.warn_scan(warnings);
+        let events = self.filestore.lock().unwrap().diff(&current);
+        if events.is_empty() {
+            return;
+        }
+        *self.filestore.lock().unwrap() = current;
+        // changes while paused or filtered out are saved too, so the next start does not
+        // replay them as changes made while moni was stopped
+        let triggers = self.filter_content(events);
+        if !triggers.is_empty() && !self.paused.get() {
+            self.handle_file_events(&triggers);
+        }
+        self.save_snapshot();
+    }
+    pub fn stats(&self) -> MoniStats {
+        self.stats.borrow().clone()
+    }
+    /// Returns false when monitaring should stop.
+    fn handle_control_events(&self, events: Vec<ControlEvent>) -> bool {
+        for event in events {
+            match event {
+                ControlEvent::Summary => self.debuger.print_summary(&self.stats.borrow()),
+                ControlEvent::Rerun => {
+                    let last_file = self.last_file.borrow().clone();
+                    match last_file {
+                        Some(filepath) => self.exe(&filepath),
+                        None => self.debuger.print_info_message(" nothing to rerun yet "),
                     }
-                })
-                .for_each(|(filepath, size)| {
-                    let mut store = self.filestore.lock().unwrap();
-                    if store.is_modify(&filepath, size) {
-                        self.exe(&filepath);
-                        store.update(filepath, size);
-                        return;
+                }
+                ControlEvent::RunAll => self.exe_all(),
+                ControlEvent::Pause => {
+                    self.paused.set(!self.paused.get());
+                    if self.paused.get() {
+                        self.debuger
+                            .print_info_message(" paused, press p to resume ")
+                    } else {
+                        self.debuger.print_info_message(" resumed ")
                     }
-                    if store.is_new(&filepath) {
-                        self.exe(&filepath);
-                        store.insert(filepath, size);
-                        return;
+                }
+                ControlEvent::Clear => self.debuger.clear(),
+                ControlEvent::ScrollUp => self.debuger.scroll(1),
+                ControlEvent::ScrollDown => self.debuger.scroll(-1),
+                ControlEvent::Help => self.debuger.print_info_message(ControlEvent::HELP),
+                ControlEvent::Shutdown => {
+                    // canceled commands are not worth a line on the way out
+                    if let Err(e) = self.scheduler.borrow_mut().cancel_all() {
+                        self.debuger.print_error_message(&format!("{:#?}", e));
                     }
-                })
+                    self.command_files.borrow_mut().clear();
+                    self.debuger.print_summary(&self.stats.borrow());
+                    self.save_snapshot();
+                    return false;
+                }
+            }
         }
+        true
     }
-    fn exe(&self, filepath: &str) {
-        if self.exe_fn.is_some() {
-            match (self.exe_fn.as_ref().unwrap())(filepath) {
-                Ok(result) => {
-                    self.debuger.print_ok_line();
-                    println!("{}", result);
-                    self.debuger.print_line();
+    fn handle_file_events(&self, events: &[FileEvent]) {
+        events
+            .iter()
+            .filter(|event| !self.is_own_file(event.path()))
+            .for_each(|event| {
+                self.debuger
+                    .print_file_event(event, self.root_of(event.path()));
+                match event {
+                    FileEvent::Created(filepath) | FileEvent::Modified(filepath) => {
+                        self.stats
+                            .borrow_mut()
+                            .record_trigger(&filepath.to_string_lossy());
+                        self.exe(filepath)
+                    }
+                    FileEvent::Removed(_) => {}
+                    FileEvent::DirCreated(dirpath) | FileEvent::DirRemoved(dirpath) => {
+                        if let Some(dir_exe_command) = self.dir_exe_command.as_ref() {
+                            self.stats
+                                .borrow_mut()
+                                .record_trigger(&dirpath.to_string_lossy());
+                            self.request(dir_exe_command, dirpath);
+                        }
+                    }
                 }
-                Err(e) => {
-                    self.debuger.print_error_line();
-                    println!("{}", e);
-                    self.debuger.print_line();
+            })
+    }
+    /// Drops the changes the content filters do not trigger on.
+    fn filter_content(&self, events: Vec<FileEvent>) -> Vec<FileEvent> {
+        let mut content_filters = self.content_filters.borrow_mut();
+        if content_filters.is_empty() {
+            return events;
+        }
+        events
+            .into_iter()
+            .filter(|event| match event {
+                FileEvent::Created(filepath) | FileEvent::Modified(filepath) => {
+                    let (root, absolute_root) = content_roots(&self.searchers, filepath);
+                    content_filters.is_triggered(filepath, &root, &absolute_root)
                 }
-            };
-            return;
+                FileEvent::Removed(filepath) => {
+                    content_filters.forget(filepath);
+                    true
+                }
+                FileEvent::DirCreated(_) | FileEvent::DirRemoved(_) => true,
+            })
+            .collect()
+    }
+    fn warn_scan(&self, warnings: Vec<String>) {
+        warnings
+            .iter()
+            .filter(|warning| !self.scan_warnings.borrow().contains(warning))
+            .for_each(|warning| self.debuger.print_warning_message(warning));
+        *self.scan_warnings.borrow_mut() = warnings;
+    }
+    fn save_snapshot(&self) {
+        if let Some(snapshot) = self.snapshot {
+            let filestore = self.filestore.lock().unwrap();
+            let filestore = filestore.map_paths(|path| self.snapshot_roots.to_snapshot(path));
+            if let Err(e) = filestore.save(snapshot) {
+                self.debuger.print_error_message(&e)
+            }
+        }
+    }
+    /// Files moni writes itself, which would trigger another execution on every write.
+    fn is_own_file(&self, filepath: &Path) -> bool {
+        [self.snapshot, self.log_file]
+            .into_iter()
+            .flatten()
+            .any(|own_file| {
+                filepath.file_name() == own_file.file_name()
+                    && filepath.canonicalize().ok() == own_file.canonicalize().ok()
+            })
+    }
+    fn run_on_start(&self) {
+        match self.run_on_start {
+            Some(RunOnStart::Once) => {
+                self.searchers
+                    .iter()
+                    .for_each(|searcher| self.exe(searcher.root()));
+                // a watch file under a workspace is run with its root already
+                self.watch_files
+                    .iter()
+                    .filter(|path| path.is_file() && self.searcher_of(path).is_none())
+                    .for_each(|path| self.exe(path));
+            }
+            Some(RunOnStart::EachFile) => self.exe_all(),
+            None => {}
         }
-        if self.exe_command.is_some() {
-            let exe_command = self
-                .exe_command
-                .as_ref()
-                .unwrap()
-                .to_execute_command(filepath);
-            self.exe_command(&exe_command);
+    }
+    /// Executes for every watched file once, watch files included, sorted by path.
+    fn exe_all(&self) {
+        let (filestore, _) = scan_filestore(&self.searchers, &self.watch_files);
+        let mut filepaths = filestore.paths().collect::<Vec<_>>();
+        filepaths.sort();
+        filepaths
+            .into_iter()
+            .for_each(|filepath| self.exe(filepath))
+    }
+    /// The workspace root `filepath` is watched under.
+    fn root_of(&self, filepath: &Path) -> Option<&Path> {
+        self.searcher_of(filepath).map(|searcher| searcher.root())
+    }
+    fn searcher_of(&self, filepath: &Path) -> Option<&FileSearcher<'a>> {
+        searcher_of(&self.searchers, filepath)
+    }
+    fn exe(&self, filepath: &Path) {
+        *self.last_file.borrow_mut() = Some(filepath.to_path_buf());
+        // only to be shown, exe_fn gets the path as it is
+        let shown_path = filepath.to_string_lossy();
+        if let Some(exe_fn) = self.exe_fn.as_ref() {
+            self.debuger.print_clear_screen();
+            self.debuger.start_run(&shown_path, Some(&shown_path));
+            let started_at = Instant::now();
+            let result = exe_fn(filepath);
+            let elapsed = started_at.elapsed();
+            self.debuger.print_fn_result(&shown_path, &result, elapsed);
+            self.finish(&ExecutionResult::from_fn_result(
+                &shown_path,
+                &result,
+                elapsed,
+            ));
             return;
         }
+        if let Some(exe_command) = self.exe_command.as_ref() {
+            self.request(exe_command, filepath);
+        }
     }
-
-    fn exe_command(&self, exe_command: &str) {
-        let mut command = target_os_command();
-        command
-            .arg("-c")
-            .arg(exe_command)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-        let status = command.status();
-        let output = command.output();
-        self.debuger.print_execute_command_line(exe_command);
-        match status {
-            Ok(status) if status.success() => {
-                self.debuger.print_ok_line();
-                println!("{}", String::from_utf8_lossy(&output.unwrap().stdout));
-                self.debuger.print_line();
+    /// Requests `exe_command` with `path` filled in from the scheduler.
+    fn request(&self, exe_command: &MoniExecuteCommand, path: &Path) {
+        let shown_path = path.to_string_lossy();
+        let exe_command = match self.root_of(path) {
+            Some(root) => exe_command.to_workspace_command(root, path),
+            None => exe_command.to_file_command(path),
+        };
+        let requested = self.scheduler.borrow_mut().request(exe_command);
+        match requested {
+            Ok((id, events)) => {
+                self.command_files
+                    .borrow_mut()
+                    .insert(id, shown_path.to_string());
+                self.handle_schedule_events(Ok(events));
             }
-            Ok(status) if !status.success() => {
-                self.debuger.print_error_line();
-                println!("{}", String::from_utf8_lossy(&output.unwrap().stderr));
-                self.debuger.print_line();
+            Err(e) => self.debuger.print_error_message(&format!("{:#?}", e)),
+        }
+    }
+
+    fn finish(&self, result: &ExecutionResult) {
+        self.stats.borrow_mut().record(result);
+        self.notify(result);
+    }
+    fn notify(&self, result: &ExecutionResult) {
+        self.notifiers.iter().for_each(|notifier| {
+            if let Err(e) = notifier.notify(result) {
+                self.debuger.print_error_message(&e)
             }
+        })
+    }
+    fn handle_schedule_events(&self, events: std::io::Result<Vec<ScheduleEvent>>) {
+        let events = match events {
+            Ok(events) => events,
             Err(e) => {
-                println!("{:#?}", e)
+                self.debuger.print_error_message(&format!("{:#?}", e));
+                return;
             }
-            _ => {
-                todo!("not impl case")
+        };
+        let policy = self.scheduler.borrow().policy();
+        for event in events {
+            match event {
+                ScheduleEvent::Started(id, command) => {
+                    self.debuger.print_clear_screen();
+                    let filepath = self.command_files.borrow().get(&id).cloned();
+                    self.debuger.start_run(&command, filepath.as_deref());
+                    self.debuger.print_execute_command_line(&command)
+                }
+                ScheduleEvent::Queued(_, command) => {
+                    self.debuger.print_schedule_line("queue", &command)
+                }
+                ScheduleEvent::Dropped(id, command) => {
+                    self.command_files.borrow_mut().remove(&id);
+                    self.debuger.print_schedule_line("drop", &command)
+                }
+                ScheduleEvent::Finished(id, result) => {
+                    self.command_files.borrow_mut().remove(&id);
+                    // results of parallel runs interleave, so say which command finished
+                    if let ConcurrencyPolicy::Parallel(_) = policy {
+                        self.debuger.print_execute_command_line(result.command());
+                    }
+                    self.debuger.print_execution_result(&result);
+                    if result.status() != ExecuteStatus::Canceled {
+                        self.finish(&result);
+                    }
+                }
+                ScheduleEvent::Output(command, chunk) => {
+                    self.debuger.print_output(&command, &chunk)
+                }
             }
         }
     }
 }
 
 #[cfg(not(target_os = "windows"))]
-fn get_file_size(filepath: &str) -> Option<u128> {
+fn get_file_size(filepath: &Path) -> Option<u128> {
     if let Ok(Ok(meta)) = File::open(filepath).map(|op| op.metadata()) {
         Some(meta_data_to_file_size(meta))
     } else {
@@ -146,7 +400,7 @@ fn get_file_size(filepath: &str) -> Option<u128> {
     }
 }
 #[cfg(target_os = "windows")]
-fn get_file_size(filepath: &str) -> Option<u128> {
+fn get_file_size(filepath: &Path) -> Option<u128> {
     use std::io::BufReader;
     use std::io::Read;
     if let Ok(file) = File::open(filepath) {
@@ -158,79 +412,248 @@ fn get_file_size(filepath: &str) -> Option<u128> {
         None
     }
 }
+fn searcher_of<'s, 'a>(
+    searchers: &'s [FileSearcher<'a>],
+    filepath: &Path,
+) -> Option<&'s FileSearcher<'a>> {
+    let roots = searchers
+        .iter()
+        .map(|searcher| searcher.root())
+        .collect::<Vec<_>>();
+    let root = find_root(&roots, filepath)?;
+    searchers.iter().find(|searcher| searcher.root() == root)
+}
+/// The roots content filters match paths from, the directory of a watched file
+/// outside every workspace.
+fn content_roots(searchers: &[FileSearcher], filepath: &Path) -> (PathBuf, PathBuf) {
+    match searcher_of(searchers, filepath) {
+        Some(searcher) => (
+            searcher.root().to_path_buf(),
+            searcher.absolute_root().to_path_buf(),
+        ),
+        None => {
+            let parent = filepath.parent().unwrap_or(Path::new("")).to_path_buf();
+            let absolute_parent = std::path::absolute(&parent).unwrap_or_else(|_| parent.clone());
+            (parent, absolute_parent)
+        }
+    }
+}
+/// One store for every workspace and watched file, so a file under nested roots is there once.
+/// Watched files that do not exist are left out, so they show up as created.
+fn scan_filestore(searchers: &[FileSearcher], watch_files: &[&Path]) -> (FileStore, Vec<String>) {
+    // sizes are read by the walking threads and sent here, so the store is not locked for each file
+    let (sender, receiver) = mpsc::channel();
+    let warnings = searchers
+        .iter()
+        .flat_map(|searcher| {
+            searcher.for_each_entry(
+                |path| {
+                    if let Some(entry) = read_entry(path) {
+                        sender.send(entry).unwrap()
+                    }
+                },
+                |dir| sender.send(ScannedEntry::Dir(dir)).unwrap(),
+            )
+        })
+        .collect();
+    let mut filestore = FileStore::new();
+    receiver
+        .try_iter()
+        .chain(
+            watch_files
+                .iter()
+                .filter_map(|path| read_entry(path.to_path_buf())),
+        )
+        .for_each(|entry| match entry {
+            ScannedEntry::File(path, size, target) => {
+                if let Some(target) = target {
+                    filestore.insert_link(path.clone(), target);
+                }
+                filestore.insert(path, size)
+            }
+            ScannedEntry::Dir(dir) => filestore.insert_dir(dir),
+        });
+    (filestore, warnings)
+}
+/// What a scan found, with what is read of it on the walking thread.
+enum ScannedEntry {
+    /// the size and the target when it is a symlink
+    File(PathBuf, u128, Option<PathBuf>),
+    Dir(PathBuf),
+}
+fn read_entry(path: PathBuf) -> Option<ScannedEntry> {
+    let size = get_file_size(&path)?;
+    let target = fs::read_link(&path).ok();
+    Some(ScannedEntry::File(path, size, target))
+}
 pub struct MoniBuilder<'a> {
     exe_command: Option<MoniExecuteCommand<'a>>,
+    dir_exe_command: Option<MoniExecuteCommand<'a>>,
     exe_fn: Option<CallBack>,
     searcher_builder: FileSearcherBuilder<'a>,
     around_secs: u64,
     around_nanos: u32,
+    timeout: Option<Duration>,
+    concurrency: ConcurrencyPolicy,
+    run_on_start: Option<RunOnStart>,
+    snapshot: Option<&'a Path>,
+    log_file: Option<&'a Path>,
+    sink: Option<Box<dyn MoniSink>>,
+    notifiers: Vec<Box<dyn Notifier>>,
+    preset: Option<Preset>,
+    workspaces: Vec<Workspace<'a>>,
+    watch_files: Vec<&'a Path>,
+    content_filters: Vec<ContentFilter>,
 }
 
+impl<'a> Default for MoniBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl<'a> MoniBuilder<'a> {
     pub fn new() -> Self {
         Self {
             exe_command: None,
+            dir_exe_command: None,
             exe_fn: None,
             around_nanos: 100_000_000,
             around_secs: 0,
+            timeout: None,
+            concurrency: ConcurrencyPolicy::default(),
+            run_on_start: None,
+            snapshot: None,
+            log_file: None,
+            sink: None,
+            notifiers: Vec::new(),
+            preset: None,
+            workspaces: Vec::new(),
+            watch_files: Vec::new(),
+            content_filters: Vec::new(),
             searcher_builder: FileSearcherBuilder::new(),
         }
     }
-    #[cfg(target_os = "windows")]
-    pub fn build_with_debuger<D: MoniDebugerConfig>(self, debuger: MoniDebuger<D>) -> Moni<'a, D> {
-        use std::intrinsics::size_of;
-
-        let searcher = self.searcher_builder.build();
-        let mut filestore = FileStore::new();
-        searcher
-            .get_all_filenames()
-            .into_iter()
-            .filter_map(|filepath| {
-                let file_path_str = filepath.as_str();
-                (file_path_str, get_file_size(file_path_str))
-            })
-            .for_each(|(path, size)| filestore.insert(path, size));
-        let filestore = Arc::new(Mutex::new(filestore));
-        Moni {
-            exe_command: self.exe_command,
-            exe_fn: self.exe_fn,
-            debuger,
-            filestore,
-            searcher,
-            around_nanos: self.around_nanos,
-            around_secs: self.around_secs,
+    pub fn build_with_debuger<D: MoniDebugerConfig>(
+        self,
+        mut debuger: MoniDebuger<D>,
+    ) -> Moni<'a, D> {
+        let searchers = self.build_searchers();
+        // the log file is opened here, so ls and explain do not create it
+        match (self.sink, self.log_file.map(FileSink::new)) {
+            (sink, Some(Ok(file_sink))) => {
+                let mut tee = TeeSink::new();
+                tee.add_sink(sink.unwrap_or_else(|| Box::new(StdoutSink)));
+                debuger.set_sink(Box::new(tee.sink(file_sink)));
+            }
+            (sink, Some(Err(e))) => {
+                if let Some(sink) = sink {
+                    debuger.set_sink(sink);
+                }
+                debuger.print_error_message(&format!(
+                    "{} can not open log file {}, so it is not written",
+                    e,
+                    self.log_file.unwrap().display()
+                ));
+            }
+            (Some(sink), None) => debuger.set_sink(sink),
+            (None, None) => {}
         }
-    }
-    #[cfg(not(target_os = "windows"))]
-    pub fn build_with_debuger<D: MoniDebugerConfig>(self, debuger: MoniDebuger<D>) -> Moni<'a, D> {
-        let searcher = self.searcher_builder.build();
-        let mut filestore = FileStore::new();
-        searcher
-            .get_all_filenames()
-            .into_iter()
-            .filter_map(|filepath| {
-                let file_path_str = filepath.as_str();
-                if let Ok(Ok(meta)) = File::open(file_path_str).map(|op| op.metadata()) {
-                    Some((filepath, meta))
-                } else {
-                    None
+        // changes made while moni was stopped show up as the first diff against the snapshot
+        let mut from_snapshot = false;
+        let snapshot_roots = SnapshotRoots::new(
+            searchers
+                .iter()
+                .map(|searcher| searcher.root())
+                .chain(self.watch_files.iter().copied()),
+        );
+        let (filestore, scan_warnings) = match self.snapshot.filter(|snapshot| snapshot.exists()) {
+            Some(snapshot) => match FileStore::from_file(snapshot) {
+                Ok(filestore) => {
+                    from_snapshot = true;
+                    let filestore = filestore.map_paths(|path| snapshot_roots.from_snapshot(path));
+                    (filestore, Vec::new())
                 }
-            })
-            .for_each(|(path, meta)| filestore.insert(path, meta_data_to_file_size(meta)));
+                Err(e) => {
+                    debuger.print_error_message(&format!(
+                        "{} can not load snapshot, so rescan workspace",
+                        e
+                    ));
+                    scan_filestore(&searchers, &self.watch_files)
+                }
+            },
+            None => scan_filestore(&searchers, &self.watch_files),
+        };
+        scan_warnings
+            .iter()
+            .for_each(|warning| debuger.print_warning_message(warning));
+        self.watch_files
+            .iter()
+            .filter(|path| !path.is_file())
+            .for_each(|path| {
+                debuger.print_info_message(&format!(
+                    " {} does not exist yet, so it is watched until it is created ",
+                    path.display()
+                ))
+            });
+        let mut content_filters = ContentFilters::new(self.content_filters);
+        // a loaded snapshot has no contents, so its first changes are not compared
+        if !from_snapshot {
+            for filepath in filestore.paths() {
+                let (root, absolute_root) = content_roots(&searchers, filepath);
+                content_filters.remember(filepath, &root, &absolute_root);
+            }
+        }
         let filestore = Arc::new(Mutex::new(filestore));
+        let exe_command = match (self.exe_command, &self.exe_fn, self.preset) {
+            (None, None, Some(preset)) => Some(MoniExecuteCommand::new(preset.execute_command())),
+            (exe_command, _, _) => exe_command,
+        };
         Moni {
-            exe_command: self.exe_command,
+            exe_command,
+            dir_exe_command: self.dir_exe_command,
             exe_fn: self.exe_fn,
             debuger,
             filestore,
-            searcher,
+            searchers,
+            watch_files: self.watch_files,
             around_nanos: self.around_nanos,
             around_secs: self.around_secs,
+            scheduler: RefCell::new(MoniScheduler::new(self.concurrency, self.timeout)),
+            run_on_start: self.run_on_start,
+            snapshot: self.snapshot,
+            snapshot_roots,
+            log_file: self.log_file,
+            notifiers: self.notifiers,
+            stats: RefCell::new(MoniStats::new()),
+            command_files: RefCell::new(HashMap::new()),
+            last_file: RefCell::new(None),
+            paused: Cell::new(false),
+            scan_warnings: RefCell::new(scan_warnings),
+            content_filters: RefCell::new(content_filters),
+        }
+    }
+    /// One searcher for each workspace, or for root when none is added.
+    pub fn build_searchers(&self) -> Vec<FileSearcher<'a>> {
+        if self.workspaces.is_empty() {
+            vec![self.searcher_builder.clone().build()]
+        } else {
+            self.workspaces
+                .iter()
+                .map(|workspace| workspace.apply(self.searcher_builder.clone()).build())
+                .collect()
         }
     }
+    /// The files that would be watched and why the others are not, without watching.
+    pub fn ls(&self) -> String {
+        listing::ls(&self.build_searchers(), &self.watch_files)
+    }
+    /// Why `path` would be watched or not, without watching.
+    pub fn explain(&self, path: &Path) -> String {
+        listing::explain(&self.build_searchers(), &self.watch_files, path)
+    }
     pub fn exe_fn<F>(mut self, exe_fn: F) -> Self
     where
-        F: Fn(&str) -> Result<String, String> + 'static,
+        F: Fn(&Path) -> Result<String, String> + 'static,
     {
         self.exe_fn = Some(Box::new(exe_fn));
         self
@@ -240,16 +663,113 @@ impl<'a> MoniBuilder<'a> {
         self.exe_command = Some(exe_command);
         self
     }
-    pub fn root(self, root: &'a str) -> Self {
+    pub fn set_exe_command(&mut self, exe_command: MoniExecuteCommand<'a>) {
+        self.exe_command = Some(exe_command);
+    }
+    /// Watches the usual files of `preset` in addition to the ones already set,
+    /// and executes its command when neither exe_command nor exe_fn is set.
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.set_preset(preset);
+        self
+    }
+    pub fn set_preset(&mut self, preset: Preset) {
+        self.searcher_builder.set_preset(preset);
+        self.preset = Some(preset);
+    }
+    /// Kills the command (and every process it spawned) when it runs longer than `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+    /// Decides what happens to changes detected while the execute command is running.
+    /// `exe_fn` always runs in place, so this only applies to the execute command.
+    pub fn concurrency(mut self, concurrency: ConcurrencyPolicy) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+    pub fn set_concurrency(&mut self, concurrency: ConcurrencyPolicy) {
+        self.concurrency = concurrency;
+    }
+    pub fn run_on_start(mut self, run_on_start: RunOnStart) -> Self {
+        self.run_on_start = Some(run_on_start);
+        self
+    }
+    pub fn set_run_on_start(&mut self, run_on_start: RunOnStart) {
+        self.run_on_start = Some(run_on_start);
+    }
+    /// Saves the watched files to `snapshot` whenever they change and diffs against it
+    /// on the next start, so changes made while moni was stopped are executed too.
+    pub fn snapshot(mut self, snapshot: &'a str) -> Self {
+        self.snapshot = Some(Path::new(snapshot));
+        self
+    }
+    pub fn set_snapshot(&mut self, snapshot: &'a str) {
+        self.snapshot = Some(Path::new(snapshot));
+    }
+    /// Sends everything moni prints to `sink` instead of stdout.
+    pub fn sink<S: MoniSink + 'static>(mut self, sink: S) -> Self {
+        self.sink = Some(Box::new(sink));
+        self
+    }
+    pub fn set_sink(&mut self, sink: Box<dyn MoniSink>) {
+        self.sink = Some(sink);
+    }
+    /// Tells `notifier` about every finished execution.
+    pub fn notifier<N: Notifier + 'static>(mut self, notifier: N) -> Self {
+        self.notifiers.push(Box::new(notifier));
+        self
+    }
+    pub fn add_notifier(&mut self, notifier: Box<dyn Notifier>) {
+        self.notifiers.push(notifier);
+    }
+    /// Appends the output to `log_file` in addition to stdout or the sink.
+    /// A log file that can not be opened is reported when building instead.
+    pub fn log_file(mut self, log_file: &'a str) -> Self {
+        self.set_log_file(log_file);
+        self
+    }
+    pub fn set_log_file(&mut self, log_file: &'a str) {
+        self.log_file = Some(Path::new(log_file));
+    }
+    pub fn root<P: AsRef<Path> + ?Sized>(self, root: &'a P) -> Self {
         let searcher_builder = self.searcher_builder.root(root);
         Self {
             searcher_builder,
             ..self
         }
     }
-    pub fn set_root(&mut self, root: &'a str) {
+    pub fn set_root<P: AsRef<Path> + ?Sized>(&mut self, root: &'a P) {
         self.searcher_builder.set_root(root);
     }
+    /// Watches `workspace` too, in the same scan as the others. Once a workspace is
+    /// added, root is only watched when it is added as a workspace as well.
+    pub fn workspace(mut self, workspace: Workspace<'a>) -> Self {
+        self.add_workspace(workspace);
+        self
+    }
+    pub fn add_workspace(&mut self, workspace: Workspace<'a>) {
+        self.workspaces.push(workspace);
+    }
+    /// Triggers only on changes that pass `content_filter`, checked after a change is found.
+    pub fn content_filter(mut self, content_filter: ContentFilter) -> Self {
+        self.add_content_filter(content_filter);
+        self
+    }
+    pub fn add_content_filter(&mut self, content_filter: ContentFilter) {
+        self.content_filters.push(content_filter);
+    }
+    /// Watches the file at `path` too, e.g. `../.env` or `/etc/myapp/config.yaml`, whether
+    /// it is under a workspace or not. A file that does not exist yet is created later.
+    pub fn watch_file(mut self, path: &'a str) -> Self {
+        self.watch_files.push(Path::new(path));
+        self
+    }
+    pub fn set_watch_files(&mut self, paths: Vec<&'a str>) {
+        self.watch_files = paths.into_iter().map(Path::new).collect();
+    }
     pub fn target_extension(self, extension: &'a str) -> Self {
         let searcher_builder = self.searcher_builder.target_extension(extension);
         Self {
@@ -284,10 +804,95 @@ impl<'a> MoniBuilder<'a> {
     pub fn set_ignore_re(&mut self, re: Vec<&'a str>) {
         self.searcher_builder.set_ignore_re(re);
     }
+    /// Watches only files matching one of the include regexes.
+    pub fn include_re(mut self, re: &'a str) -> Self {
+        self.searcher_builder = self.searcher_builder.include_re(re);
+        self
+    }
+    pub fn set_include_re(&mut self, re: Vec<&'a str>) {
+        self.searcher_builder.set_include_re(re);
+    }
     pub fn set_target_extensions(&mut self, target_extensions: Vec<&'a str>) {
         self.searcher_builder
             .set_target_extensions(target_extensions);
     }
+    pub fn set_ignore_extensions(&mut self, extensions: Vec<&'a str>) {
+        self.searcher_builder.set_ignore_extensions(extensions);
+    }
+    /// Matches target and ignore extensions regardless of case, e.g. `jpg` and `JPG`.
+    pub fn case_insensitive_extensions(mut self, case_insensitive: bool) -> Self {
+        self.set_case_insensitive_extensions(case_insensitive);
+        self
+    }
+    pub fn set_case_insensitive_extensions(&mut self, case_insensitive: bool) {
+        self.searcher_builder
+            .set_case_insensitive_extensions(case_insensitive);
+    }
+    /// Watches directories too, so creating or removing one is an event even while it is empty.
+    pub fn watch_dirs(mut self, watch_dirs: bool) -> Self {
+        self.set_watch_dirs(watch_dirs);
+        self
+    }
+    pub fn set_watch_dirs(&mut self, watch_dirs: bool) {
+        self.searcher_builder.set_watch_dirs(watch_dirs);
+    }
+    /// Watches only directories matching one of the regexes, when watch_dirs is set.
+    pub fn dir_include_re(mut self, re: &'a str) -> Self {
+        self.searcher_builder = self.searcher_builder.dir_include_re(re);
+        self
+    }
+    pub fn set_dir_include_re(&mut self, re: Vec<&'a str>) {
+        self.searcher_builder.set_dir_include_re(re);
+    }
+    /// Executes `dir_exe_command` when a watched directory is created or removed.
+    /// Without it directory events are only reported.
+    pub fn dir_exe_command(mut self, dir_exe_command: MoniExecuteCommand<'a>) -> Self {
+        self.set_dir_exe_command(dir_exe_command);
+        self
+    }
+    pub fn set_dir_exe_command(&mut self, dir_exe_command: MoniExecuteCommand<'a>) {
+        self.dir_exe_command = Some(dir_exe_command);
+    }
+    /// Watches files in symlinked directories too, walking each directory once.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.set_follow_symlinks(follow_symlinks);
+        self
+    }
+    pub fn set_follow_symlinks(&mut self, follow_symlinks: bool) {
+        self.searcher_builder.set_follow_symlinks(follow_symlinks);
+    }
+    /// Does not watch directories deeper than `max_depth` below each root.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.set_max_depth(max_depth);
+        self
+    }
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.searcher_builder.set_max_depth(max_depth);
+    }
+    /// Does not watch files larger than `max_file_size` bytes, e.g. huge binaries.
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.set_max_file_size(max_file_size);
+        self
+    }
+    pub fn set_max_file_size(&mut self, max_file_size: u64) {
+        self.searcher_builder.set_max_file_size(max_file_size);
+    }
+    /// Watches at most `max_files` files of each root and warns about the rest.
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.set_max_files(max_files);
+        self
+    }
+    pub fn set_max_files(&mut self, max_files: usize) {
+        self.searcher_builder.set_max_files(max_files);
+    }
+    /// Does not cross mount points below each root.
+    pub fn same_filesystem(mut self, same_filesystem: bool) -> Self {
+        self.set_same_filesystem(same_filesystem);
+        self
+    }
+    pub fn set_same_filesystem(&mut self, same_filesystem: bool) {
+        self.searcher_builder.set_same_filesystem(same_filesystem);
+    }
 }
 
 #[cfg(not(target_os = "windows"))]
@@ -295,15 +900,386 @@ fn meta_data_to_file_size(metadata: Metadata) -> u128 {
     metadata.size() as u128
 }
 
-#[cfg(target_os = "linux")]
-fn target_os_command() -> Command {
-    Command::new("bash")
-}
-#[cfg(target_os = "windows")]
-fn target_os_command() -> Command {
-    Command::new("bash")
-}
-#[cfg(target_os = "macos")]
-fn target_os_command() -> Command {
-    Command::new("zsh")
+#[cfg(test)]
+mod test_moni {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::parts::sink::BufferSink;
+
+    struct MockNotifier {
+        results: Rc<RefCell<Vec<ExecutionResult>>>,
+    }
+    impl Notifier for MockNotifier {
+        fn notify(&self, result: &ExecutionResult) -> Result<(), String> {
+            self.results.borrow_mut().push(result.clone());
+            Ok(())
+        }
+    }
+    #[test]
+    fn test_exe_notifies_result() {
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .exe_fn(|filepath| Err(format!("{} failed", filepath.display())))
+            .notifier(MockNotifier {
+                results: results.clone(),
+            })
+            .sink(BufferSink::new())
+            .build_with_debuger(MoniDebuger::default());
+        moni.exe(Path::new("test.rs"));
+        let results = results.borrow();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].command(), "test.rs");
+        assert_eq!(results[0].status(), ExecuteStatus::Error);
+        assert_eq!(results[0].stderr(), "test.rs failed");
+        assert_eq!(moni.stats().runs(), 1);
+        assert_eq!(moni.stats().failures(), 1);
+    }
+    #[test]
+    fn test_control_events() {
+        let executed = Rc::new(RefCell::new(Vec::new()));
+        let recorder = executed.clone();
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .exe_fn(move |filepath| {
+                recorder.borrow_mut().push(filepath.to_path_buf());
+                Ok(String::new())
+            })
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        assert!(moni.handle_control_events(vec![ControlEvent::Rerun]));
+        assert!(buffer.contents().contains("nothing to rerun yet"));
+        moni.exe(Path::new("./tests/test.rs"));
+        assert!(moni.handle_control_events(vec![ControlEvent::Rerun, ControlEvent::Pause]));
+        assert_eq!(*executed.borrow(), vec![Path::new("./tests/test.rs"); 2]);
+        assert!(moni.paused.get());
+        assert!(!moni.handle_control_events(vec![ControlEvent::Shutdown]));
+    }
+    #[test]
+    fn test_control_events_case_pause_and_resume() {
+        let dir = std::env::temp_dir().join(format!("monirs-test-pause-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let root = dir.to_str().unwrap().to_string();
+        let executed = Rc::new(RefCell::new(Vec::new()));
+        let recorder = executed.clone();
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .root(&root)
+            .exe_fn(move |filepath| {
+                recorder.borrow_mut().push(filepath.to_path_buf());
+                Ok(String::new())
+            })
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        assert!(moni.handle_control_events(vec![ControlEvent::Pause]));
+        assert!(moni.paused.get());
+        assert!(buffer.contents().contains("paused, press p to resume"));
+        fs::write(dir.join("a.txt"), "paused").unwrap();
+        moni.scan_changes();
+        assert!(executed.borrow().is_empty());
+        assert!(moni.handle_control_events(vec![ControlEvent::Pause]));
+        assert!(!moni.paused.get());
+        assert!(buffer.contents().contains("resumed"));
+        fs::write(dir.join("a.txt"), "resumed").unwrap();
+        moni.scan_changes();
+        assert_eq!(*executed.borrow(), vec![dir.join("a.txt")]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_control_events_case_clear() {
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .exe_fn(|_| Ok(String::new()))
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default().clear_lines(3));
+        assert!(moni.handle_control_events(vec![ControlEvent::Clear]));
+        assert_eq!(buffer.contents(), "\n\n\n");
+    }
+    #[test]
+    fn test_snapshot_case_paused_and_filtered() {
+        let dir = std::env::temp_dir().join(format!("monirs-test-snapshot-{}", std::process::id()));
+        let root = dir.join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.md"), "a").unwrap();
+        let snapshot = dir.join("state.json");
+        let (root_str, snapshot_str) = (
+            root.to_str().unwrap().to_string(),
+            snapshot.to_str().unwrap().to_string(),
+        );
+        let moni = MoniBuilder::new()
+            .root(&root_str)
+            .snapshot(&snapshot_str)
+            .content_filter(ContentFilter::new().marker("@moni"))
+            .exe_fn(|_| Ok(String::new()))
+            .sink(BufferSink::new())
+            .build_with_debuger(MoniDebuger::default());
+        // filtered out by the marker
+        fs::write(root.join("b.md"), "b").unwrap();
+        moni.scan_changes();
+        assert!(!FileStore::from_file(&snapshot)
+            .unwrap()
+            .is_new(&root.join("b.md")));
+        assert!(moni.handle_control_events(vec![ControlEvent::Pause]));
+        fs::write(root.join("a.md"), "@moni changed").unwrap();
+        moni.scan_changes();
+        assert!(!moni.handle_control_events(vec![ControlEvent::Shutdown]));
+        let (current, _) = scan_filestore(&moni.searchers, &moni.watch_files);
+        assert!(FileStore::from_file(&snapshot)
+            .unwrap()
+            .diff(&current)
+            .is_empty());
+        assert_eq!(moni.stats().runs(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_snapshot_case_other_spelling_of_root() {
+        let dir = std::env::temp_dir().join(format!(
+            "monirs-test-snapshot-spelling-{}",
+            std::process::id()
+        ));
+        let root = dir.join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(dir.join("x")).unwrap();
+        fs::write(root.join("a.md"), "a").unwrap();
+        fs::write(root.join("b.md"), "b").unwrap();
+        // unchanged, so it runs only if the snapshot keys miss the other spelling
+        fs::write(root.join("c.md"), "c").unwrap();
+        let snapshot = dir.join("state.json");
+        let (root_str, other_root_str, snapshot_str) = (
+            root.to_str().unwrap().to_string(),
+            dir.join("x/../root").to_str().unwrap().to_string(),
+            snapshot.to_str().unwrap().to_string(),
+        );
+        let moni = MoniBuilder::new()
+            .root(&root_str)
+            .snapshot(&snapshot_str)
+            .exe_fn(|_| Ok(String::new()))
+            .sink(BufferSink::new())
+            .build_with_debuger(MoniDebuger::default());
+        moni.save_snapshot();
+        fs::write(root.join("a.md"), "changed").unwrap();
+        fs::remove_file(root.join("b.md")).unwrap();
+        let executed = Arc::new(Mutex::new(Vec::new()));
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .root(&other_root_str)
+            .snapshot(&snapshot_str)
+            .exe_fn({
+                let executed = executed.clone();
+                move |path| {
+                    executed.lock().unwrap().push(path.to_path_buf());
+                    Ok(String::new())
+                }
+            })
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        moni.scan_changes();
+        assert_eq!(
+            *executed.lock().unwrap(),
+            vec![Path::new(&other_root_str).join("a.md")]
+        );
+        assert!(buffer.contents().contains("removed"));
+        assert!(buffer.contents().contains("b.md"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_command_files_case_dropped() {
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .exe_command(MoniExecuteCommand::new("sleep 0.2; echo MONI_FILE_PATH"))
+            .concurrency(ConcurrencyPolicy::Drop)
+            .sink(BufferSink::new())
+            .build_with_debuger(MoniDebuger::default());
+        moni.exe(Path::new("./tests/test.rs"));
+        moni.exe(Path::new("./tests/test2/test2.txt"));
+        assert_eq!(
+            moni.command_files.borrow().values().collect::<Vec<_>>(),
+            vec!["./tests/test.rs"]
+        );
+        while moni.scheduler.borrow().is_busy() {
+            let events = moni.scheduler.borrow_mut().poll();
+            moni.handle_schedule_events(events);
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(moni.command_files.borrow().is_empty());
+    }
+    #[test]
+    fn test_run_on_start_new() {
+        assert_eq!(RunOnStart::new("once"), Ok(RunOnStart::Once));
+        assert_eq!(RunOnStart::new("each_file"), Ok(RunOnStart::EachFile));
+        assert!(RunOnStart::new("always").is_err());
+    }
+    fn executed_on_start(run_on_start: RunOnStart) -> Vec<PathBuf> {
+        let executed = Rc::new(RefCell::new(Vec::new()));
+        let recorder = executed.clone();
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .watch_file("./Cargo.toml")
+            .watch_file("./tests/test.rs")
+            .watch_file("./not-created-yet.env")
+            .run_on_start(run_on_start)
+            .exe_fn(move |filepath| {
+                recorder.borrow_mut().push(filepath.to_path_buf());
+                Ok(String::new())
+            })
+            .sink(BufferSink::new())
+            .build_with_debuger(MoniDebuger::default());
+        moni.run_on_start();
+        let executed = executed.borrow().clone();
+        executed
+    }
+    #[test]
+    fn test_run_on_start_case_once() {
+        assert_eq!(
+            executed_on_start(RunOnStart::Once),
+            vec![PathBuf::from("./tests"), PathBuf::from("./Cargo.toml")]
+        );
+    }
+    #[test]
+    fn test_run_on_start_case_each_file() {
+        assert_eq!(
+            executed_on_start(RunOnStart::EachFile),
+            vec![
+                PathBuf::from("./Cargo.toml"),
+                PathBuf::from("./tests/test.rs"),
+                PathBuf::from("./tests/test1/test1-1/test1-1-1/test.txt"),
+                PathBuf::from("./tests/test2/test2.txt"),
+            ]
+        );
+    }
+    #[test]
+    fn test_workspaces() {
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .target_extension("rs")
+            .workspace(Workspace::new("./tests/test1"))
+            .workspace(Workspace::new("./tests/test2").target_extensions(vec!["txt"]))
+            .exe_command(MoniExecuteCommand::new("echo {root} {relpath}"))
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        let filestore = moni.filestore.lock().unwrap().clone();
+        assert!(!filestore.is_new(Path::new("./tests/test2/test2.txt")));
+        assert!(filestore.is_new(Path::new("./tests/test1/test1-1/test1-1-1/test.txt")));
+        assert_eq!(
+            moni.root_of(Path::new("./tests/test1/test1-1/a.rs")),
+            Some(Path::new("./tests/test1"))
+        );
+        moni.exe(Path::new("./tests/test2/test2.txt"));
+        assert!(buffer.contents().contains("echo ./tests/test2 test2.txt"));
+    }
+    #[test]
+    fn test_workspaces_case_outside() {
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .workspace(Workspace::new("./tests/test1"))
+            .watch_file("./Cargo.toml")
+            .exe_command(MoniExecuteCommand::new("echo {root} {relpath}"))
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        assert_eq!(moni.root_of(Path::new("./Cargo.toml")), None);
+        moni.exe(Path::new("./Cargo.toml"));
+        assert!(buffer.contents().contains("echo . Cargo.toml"));
+    }
+    #[test]
+    fn test_filter_content() {
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .content_filter(ContentFilter::new().path("\\.txt$").marker("@moni"))
+            .exe_fn(|_| Ok(String::new()))
+            .sink(BufferSink::new())
+            .build_with_debuger(MoniDebuger::default());
+        let txt = PathBuf::from("./tests/test2/test2.txt");
+        let rs = PathBuf::from("./tests/test.rs");
+        let events = moni.filter_content(vec![
+            FileEvent::Modified(txt.clone()),
+            FileEvent::Modified(rs.clone()),
+            FileEvent::Removed(txt.clone()),
+        ]);
+        assert_eq!(
+            events,
+            vec![FileEvent::Modified(rs), FileEvent::Removed(txt)]
+        );
+    }
+    #[test]
+    fn test_dir_events() {
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .watch_dirs(true)
+            .dir_include_re("^test2$")
+            .exe_fn(|_| Ok(String::new()))
+            .dir_exe_command(MoniExecuteCommand::new("echo dir {relpath}"))
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        let dir = PathBuf::from("./tests/test2");
+        let removed = moni.filestore.lock().unwrap().diff(&FileStore::new());
+        assert!(removed.contains(&FileEvent::DirRemoved(dir.clone())));
+        assert!(!removed.contains(&FileEvent::DirRemoved(PathBuf::from("./tests/test1"))));
+        moni.handle_file_events(&[FileEvent::DirCreated(dir)]);
+        assert!(buffer.contents().contains("echo dir test2"));
+    }
+    #[test]
+    fn test_watch_files() {
+        let later = std::env::temp_dir().join(format!("monirs-watch-file-{}", std::process::id()));
+        let later_str = later.to_str().unwrap().to_string();
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .watch_file("./Cargo.toml")
+            .watch_file(&later_str)
+            .exe_command(MoniExecuteCommand::new("echo {}"))
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        assert!(buffer.contents().contains("does not exist yet"));
+        let filestore = moni.filestore.lock().unwrap().clone();
+        assert!(!filestore.is_new(Path::new("./Cargo.toml")));
+        fs::write(&later, "KEY=1").unwrap();
+        let (current, _) = scan_filestore(&moni.searchers, &moni.watch_files);
+        fs::remove_file(&later).unwrap();
+        assert_eq!(filestore.diff(&current), vec![FileEvent::Created(later)]);
+    }
+    #[test]
+    fn test_log_file() {
+        let dir = std::env::temp_dir().join(format!("monirs-test-log-{}", std::process::id()));
+        let log_file = dir.join("moni.log");
+        let log_file_str = log_file.to_str().unwrap().to_string();
+        let builder = MoniBuilder::new()
+            .root("./tests")
+            .exe_fn(|_| Ok(String::new()))
+            .log_file(&log_file_str);
+        assert!(builder.ls().contains("./tests/test.rs"));
+        assert!(!dir.exists());
+        let buffer = BufferSink::new();
+        builder
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        assert!(buffer.contents().contains("can not open log file"));
+        fs::create_dir_all(&dir).unwrap();
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .exe_fn(|_| Ok(String::new()))
+            .log_file(&log_file_str)
+            .sink(BufferSink::new())
+            .build_with_debuger(MoniDebuger::default());
+        moni.handle_control_events(vec![ControlEvent::Help]);
+        assert!(fs::read_to_string(&log_file).unwrap().contains("pause"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_warn_scan_once() {
+        let buffer = BufferSink::new();
+        let moni = MoniBuilder::new()
+            .root("./tests")
+            .max_files(1)
+            .exe_fn(|_| Ok(String::new()))
+            .sink(buffer.clone())
+            .build_with_debuger(MoniDebuger::default());
+        let (_, warnings) = scan_filestore(&moni.searchers, &moni.watch_files);
+        moni.warn_scan(warnings);
+        assert_eq!(buffer.contents().matches("max_files").count(), 1);
+    }
 }
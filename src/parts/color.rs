@@ -0,0 +1,143 @@
+use std::env;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Bold,
+    Dim,
+    None,
+}
+impl Color {
+    pub fn new(color: &str) -> Result<Self, String> {
+        match color {
+            "black" => Ok(Self::Black),
+            "red" => Ok(Self::Red),
+            "green" => Ok(Self::Green),
+            "yellow" => Ok(Self::Yellow),
+            "blue" => Ok(Self::Blue),
+            "magenta" => Ok(Self::Magenta),
+            "cyan" => Ok(Self::Cyan),
+            "white" => Ok(Self::White),
+            "bold" => Ok(Self::Bold),
+            "dim" => Ok(Self::Dim),
+            "none" => Ok(Self::None),
+            _ => Err(format!(
+                "{} is not color. use black, red, green, yellow, blue, magenta, cyan, white, bold, dim or none",
+                color
+            )),
+        }
+    }
+    fn code(&self) -> Option<&'static str> {
+        match *self {
+            Self::Black => Some("30"),
+            Self::Red => Some("31"),
+            Self::Green => Some("32"),
+            Self::Yellow => Some("33"),
+            Self::Blue => Some("34"),
+            Self::Magenta => Some("35"),
+            Self::Cyan => Some("36"),
+            Self::White => Some("37"),
+            Self::Bold => Some("1"),
+            Self::Dim => Some("2"),
+            Self::None => None,
+        }
+    }
+    /// Wraps every line on its own, so a reset is never cut off by a line break.
+    pub fn paint(&self, text: &str) -> String {
+        match self.code() {
+            Some(code) => text
+                .split('\n')
+                .map(|line| {
+                    if line.is_empty() {
+                        line.to_string()
+                    } else {
+                        format!("\x1b[{}m{}\x1b[0m", code, line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => text.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ColorMode {
+    /// color only when writing to a terminal and NO_COLOR is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+impl ColorMode {
+    pub fn new(mode: &str) -> Result<Self, String> {
+        match mode {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "{} is not color mode. use auto, always or never",
+                mode
+            )),
+        }
+    }
+    pub fn is_color(&self, is_terminal: bool) -> bool {
+        match *self {
+            Self::Always => true,
+            Self::Never => false,
+            // https://no-color.org
+            Self::Auto => is_terminal && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+        }
+    }
+}
+
+/// Colors of each MoniDebuger line. Output of the command itself is never painted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MoniTheme {
+    pub start: Color,
+    pub success: Color,
+    pub error: Color,
+    pub timeout: Color,
+    pub warning: Color,
+    pub execute: Color,
+    pub schedule: Color,
+    pub line: Color,
+}
+impl Default for MoniTheme {
+    fn default() -> Self {
+        Self {
+            start: Color::Cyan,
+            success: Color::Green,
+            error: Color::Red,
+            timeout: Color::Magenta,
+            warning: Color::Yellow,
+            execute: Color::Yellow,
+            schedule: Color::Yellow,
+            line: Color::Dim,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_color {
+    use super::*;
+    #[test]
+    fn test_paint() {
+        assert_eq!(Color::Green.paint(" ok "), "\x1b[32m ok \x1b[0m");
+        assert_eq!(Color::Red.paint("\nerror\n"), "\n\x1b[31merror\x1b[0m\n");
+        assert_eq!(Color::None.paint(" ok "), " ok ");
+    }
+    #[test]
+    fn test_color_mode() {
+        assert!(ColorMode::Always.is_color(false));
+        assert!(!ColorMode::Never.is_color(true));
+        assert!(!ColorMode::Auto.is_color(false));
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    collections::HashMap,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use super::pattern::PathPattern;
+
+/// Bytes sniffed for a NUL, like git does to tell binary files.
+const SNIFF_LEN: usize = 8000;
+
+/// Decides from the content of a changed file whether it triggers an execution.
+#[derive(Debug, Clone, Default)]
+pub struct ContentFilter {
+    path: Option<PathPattern>,
+    ignore_lines: Option<Regex>,
+    marker: Option<String>,
+    skip_binary: bool,
+}
+impl ContentFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Applies only to files matching `re`, a regex like ignore_re. Every file without it.
+    pub fn path(mut self, re: &str) -> Self {
+        self.path = Some(PathPattern::new(re).unwrap_or_else(|e| panic!("{}", e)));
+        self
+    }
+    /// Does not trigger when only lines matching `re` changed, e.g. `^// generated at`.
+    pub fn ignore_lines(mut self, re: &str) -> Self {
+        self.ignore_lines =
+            Some(Regex::new(re).unwrap_or_else(|e| panic!("{} is not regex: {}", re, e)));
+        self
+    }
+    /// Triggers only when the new content contains `marker`.
+    pub fn marker(mut self, marker: &str) -> Self {
+        self.marker = Some(marker.to_string());
+        self
+    }
+    /// Does not trigger for files with a NUL byte near the start.
+    pub fn skip_binary(mut self, skip_binary: bool) -> Self {
+        self.skip_binary = skip_binary;
+        self
+    }
+    fn is_applied(&self, path: &Path, root: &Path, absolute_root: &Path) -> bool {
+        self.path
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(path, root, absolute_root))
+    }
+}
+
+/// The content filters of a Moni, with what they need to remember between changes.
+#[derive(Debug, Default)]
+pub struct ContentFilters {
+    filters: Vec<ContentFilter>,
+    /// hash of the lines not ignored, by the index of the filter and the file
+    hashes: HashMap<(usize, PathBuf), u64>,
+}
+impl ContentFilters {
+    pub fn new(filters: Vec<ContentFilter>) -> Self {
+        Self {
+            filters,
+            hashes: HashMap::new(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+    /// Whether the created or modified `path` passes every filter applied to it.
+    /// Files that can not be read pass, so the execution reports the error.
+    pub fn is_triggered(&mut self, path: &Path, root: &Path, absolute_root: &Path) -> bool {
+        let applied = self.applied(path, root, absolute_root, false);
+        if applied.is_empty() {
+            return true;
+        }
+        let Ok(content) = fs::read(path) else {
+            return true;
+        };
+        // every filter is checked, so the hashes stay current
+        let passed = applied
+            .into_iter()
+            .map(|index| self.is_passed(index, path, &content))
+            .collect::<Vec<_>>();
+        passed.into_iter().all(|passed| passed)
+    }
+    /// Remembers the lines of `path` that are not ignored, so the first change of a
+    /// file watched from the start can be compared too.
+    pub fn remember(&mut self, path: &Path, root: &Path, absolute_root: &Path) {
+        let applied = self.applied(path, root, absolute_root, true);
+        if applied.is_empty() {
+            return;
+        }
+        if let Ok(content) = fs::read(path) {
+            applied.into_iter().for_each(|index| {
+                self.is_passed(index, path, &content);
+            });
+        }
+    }
+    pub fn forget(&mut self, path: &Path) {
+        self.hashes.retain(|(_, hashed), _| hashed != path);
+    }
+    fn applied(
+        &self,
+        path: &Path,
+        root: &Path,
+        absolute_root: &Path,
+        only_ignore_lines: bool,
+    ) -> Vec<usize> {
+        self.filters
+            .iter()
+            .enumerate()
+            .filter(|(_, filter)| !only_ignore_lines || filter.ignore_lines.is_some())
+            .filter(|(_, filter)| filter.is_applied(path, root, absolute_root))
+            .map(|(index, _)| index)
+            .collect()
+    }
+    fn is_passed(&mut self, index: usize, path: &Path, content: &[u8]) -> bool {
+        let filter = &self.filters[index];
+        if filter.skip_binary && is_binary(content) {
+            return false;
+        }
+        let has_marker = filter
+            .marker
+            .as_ref()
+            .is_none_or(|marker| String::from_utf8_lossy(content).contains(marker.as_str()));
+        let Some(ignore_lines) = &filter.ignore_lines else {
+            return has_marker;
+        };
+        let hash = hash_lines(content, ignore_lines);
+        let previous = self.hashes.insert((index, path.to_path_buf()), hash);
+        has_marker && previous != Some(hash)
+    }
+}
+
+fn is_binary(content: &[u8]) -> bool {
+    content[..content.len().min(SNIFF_LEN)].contains(&0)
+}
+fn hash_lines(content: &[u8], ignore_lines: &Regex) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    String::from_utf8_lossy(content)
+        .lines()
+        .filter(|line| !ignore_lines.is_match(line))
+        .for_each(|line| line.hash(&mut hasher));
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test_content_filter {
+    use std::env;
+
+    use super::*;
+
+    /// One directory for each test, as tests run in parallel and each removes its own.
+    fn test_dir(test: &str) -> PathBuf {
+        env::temp_dir().join(format!(
+            "monirs-content-filter-{}-{}",
+            test,
+            std::process::id()
+        ))
+    }
+    fn write(dir: &Path, name: &str, content: &[u8]) -> PathBuf {
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+    fn is_triggered(filters: &mut ContentFilters, path: &Path) -> bool {
+        let root = path.parent().unwrap();
+        filters.is_triggered(path, root, root)
+    }
+    #[test]
+    fn test_ignore_lines() {
+        let dir = test_dir("ignore-lines");
+        let path = write(
+            &dir,
+            "generated.rs",
+            b"// generated at 10:00\nfn main() {}\n",
+        );
+        let mut filters = ContentFilters::new(vec![ContentFilter::new()
+            .path("^generated")
+            .ignore_lines("^// generated at")]);
+        let root = path.parent().unwrap();
+        filters.remember(&path, root, root);
+        fs::write(&path, b"// generated at 10:01\nfn main() {}\n").unwrap();
+        assert!(!is_triggered(&mut filters, &path));
+        fs::write(&path, b"// generated at 10:02\nfn main() { run() }\n").unwrap();
+        assert!(is_triggered(&mut filters, &path));
+        filters.forget(&path);
+        assert!(is_triggered(&mut filters, &path));
+        let other = write(&dir, "other.rs", b"fn main() {}\n");
+        assert!(is_triggered(&mut filters, &other));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_marker_and_skip_binary() {
+        let dir = test_dir("marker");
+        let mut filters = ContentFilters::new(vec![
+            ContentFilter::new().path("\\.md$").marker("@moni"),
+            ContentFilter::new().skip_binary(true),
+        ]);
+        assert!(!is_triggered(&mut filters, &write(&dir, "a.md", b"draft")));
+        assert!(is_triggered(
+            &mut filters,
+            &write(&dir, "b.md", b"@moni build")
+        ));
+        assert!(!is_triggered(
+            &mut filters,
+            &write(&dir, "c.png", b"\x89PNG\0\0")
+        ));
+        assert!(is_triggered(&mut filters, &write(&dir, "d.txt", b"text")));
+        let removed = write(&dir, "e.txt", b"");
+        fs::remove_file(&removed).unwrap();
+        assert!(is_triggered(&mut filters, &removed));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    io::{self, IsTerminal, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        OnceLock,
+    },
+    thread,
+};
+
+/// What the person running moni asked for, like the watch mode of jest or vitest.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ControlEvent {
+    /// `q` or Ctrl-C, stop monitaring after printing the summary
+    Shutdown,
+    /// `s`, print the session summary
+    Summary,
+    /// `r`, execute the last file again
+    Rerun,
+    /// `a`, execute every watched file
+    RunAll,
+    /// `p`, stop or start reacting to changes
+    Pause,
+    /// `c`, clear the terminal
+    Clear,
+    /// `?`, print the keys
+    Help,
+    /// `k`, scroll the output of the dashboard up
+    ScrollUp,
+    /// `j`, scroll the output of the dashboard down
+    ScrollDown,
+}
+impl ControlEvent {
+    pub fn from_key(key: char) -> Option<Self> {
+        match key {
+            'q' => Some(Self::Shutdown),
+            's' => Some(Self::Summary),
+            'r' => Some(Self::Rerun),
+            'a' => Some(Self::RunAll),
+            'p' => Some(Self::Pause),
+            'c' => Some(Self::Clear),
+            '?' => Some(Self::Help),
+            'k' => Some(Self::ScrollUp),
+            'j' => Some(Self::ScrollDown),
+            _ => None,
+        }
+    }
+    pub const HELP: &'static str =
+        " r rerun, a run all files, p pause/resume, c clear, s summary, q quit, ? help ";
+}
+
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+/// Ctrl-C was pressed before, so the next one does not wait for the watch loop.
+#[cfg(unix)]
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// The terminal before `KeyMode`, put back when a second Ctrl-C ends moni without drops.
+#[cfg(unix)]
+static ORIGINAL_TERMIOS: OnceLock<libc::termios> = OnceLock::new();
+
+/// Collects Ctrl-C and keys pressed on the terminal without blocking the monitaring loop.
+pub struct MoniControl {
+    keys: Option<Receiver<char>>,
+    // restores the terminal when monitaring stops
+    _key_mode: Option<KeyMode>,
+}
+impl MoniControl {
+    /// Catches Ctrl-C and, when stdin is a terminal, reads single keys in a thread.
+    pub fn listen() -> Self {
+        catch_shutdown();
+        if !io::stdin().is_terminal() {
+            return Self {
+                keys: None,
+                _key_mode: None,
+            };
+        }
+        let key_mode = KeyMode::enable();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for byte in io::stdin().lock().bytes() {
+                let Ok(byte) = byte else { break };
+                if sender.send(byte as char).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            keys: Some(receiver),
+            _key_mode: key_mode,
+        }
+    }
+    pub fn poll(&self) -> Vec<ControlEvent> {
+        let mut events = self
+            .keys
+            .iter()
+            .flat_map(|keys| keys.try_iter())
+            .filter_map(ControlEvent::from_key)
+            .collect::<Vec<_>>();
+        if SHUTDOWN.swap(false, Ordering::SeqCst) {
+            events.push(ControlEvent::Shutdown);
+        }
+        events
+    }
+}
+
+/// Terminal without line buffering and echo, so a key arrives without enter.
+/// Signals stay on, so Ctrl-C still goes through `catch_shutdown`.
+#[cfg(unix)]
+struct KeyMode {
+    original: libc::termios,
+}
+#[cfg(unix)]
+impl KeyMode {
+    fn enable() -> Option<Self> {
+        // SAFETY: tcgetattr and tcsetattr only read and write the termios they are given
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return None;
+            }
+            let mut termios = original;
+            termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+            termios.c_cc[libc::VMIN] = 1;
+            termios.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) != 0 {
+                return None;
+            }
+            let _ = ORIGINAL_TERMIOS.set(original);
+            Some(Self { original })
+        }
+    }
+}
+#[cfg(unix)]
+impl Drop for KeyMode {
+    fn drop(&mut self) {
+        // SAFETY: see enable
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+/// Keys arrive after enter where the terminal mode can not be changed.
+#[cfg(not(unix))]
+struct KeyMode;
+#[cfg(not(unix))]
+impl KeyMode {
+    fn enable() -> Option<Self> {
+        None
+    }
+}
+
+/// The first Ctrl-C asks the watch loop to shut down, which it sees once per tick.
+/// A command that hangs keeps the loop from getting there, so a second Ctrl-C puts
+/// the terminal back, restores the default handler and raises SIGINT again, which
+/// ends moni at once.
+#[cfg(unix)]
+fn catch_shutdown() {
+    extern "C" fn on_sigint(_: libc::c_int) {
+        if !INTERRUPTED.swap(true, Ordering::SeqCst) {
+            SHUTDOWN.store(true, Ordering::SeqCst);
+            return;
+        }
+        // SAFETY: tcsetattr, signal and raise are async-signal-safe
+        unsafe {
+            if let Some(original) = ORIGINAL_TERMIOS.get() {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+            }
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+            libc::raise(libc::SIGINT);
+        }
+    }
+    // SAFETY: the handler only touches atomics and async-signal-safe calls
+    unsafe {
+        libc::signal(libc::SIGINT, on_sigint as *const () as libc::sighandler_t);
+    }
+}
+#[cfg(not(unix))]
+fn catch_shutdown() {}
+
+#[cfg(test)]
+mod test_control {
+    use super::*;
+    #[test]
+    fn test_from_key() {
+        assert_eq!(ControlEvent::from_key('s'), Some(ControlEvent::Summary));
+        assert_eq!(ControlEvent::from_key('q'), Some(ControlEvent::Shutdown));
+        assert_eq!(ControlEvent::from_key('p'), Some(ControlEvent::Pause));
+        assert_eq!(ControlEvent::from_key('\n'), None);
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_catch_shutdown_case_second_sigint() {
+        // SAFETY: the forked child only calls async-signal-safe functions before _exit
+        unsafe {
+            let pid = libc::fork();
+            assert!(pid >= 0);
+            if pid == 0 {
+                catch_shutdown();
+                libc::raise(libc::SIGINT);
+                if !SHUTDOWN.load(Ordering::SeqCst) {
+                    libc::_exit(1);
+                }
+                libc::raise(libc::SIGINT);
+                libc::_exit(0);
+            }
+            let mut status = 0;
+            assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+            assert!(libc::WIFSIGNALED(status));
+            assert_eq!(libc::WTERMSIG(status), libc::SIGINT);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// Last run of a rule, one row of the dashboard.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RuleStatus {
+    name: String,
+    command: String,
+    status: String,
+    exit_code: Option<i32>,
+    duration: Option<Duration>,
+    runs: usize,
+    failures: usize,
+}
+
+/// State of `moni --tui`. MoniDebuger fills it instead of printing lines, and it is
+/// drawn once per monitaring tick.
+#[derive(Default)]
+pub struct Dashboard {
+    rules: Vec<RuleStatus>,
+    events: VecDeque<String>,
+    output: VecDeque<String>,
+    /// lines scrolled up from the end of the output
+    scroll: usize,
+    /// printed after the terminal is restored, e.g. the session summary
+    farewell: Option<String>,
+    #[cfg(feature = "tui")]
+    terminal: Option<ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>>,
+}
+impl Dashboard {
+    const MAX_EVENTS: usize = 200;
+    const MAX_OUTPUT_LINES: usize = 5000;
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push_event(&mut self, event: &str) {
+        self.events.push_back(event.trim().to_string());
+        if self.events.len() > Self::MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+    pub fn push_output(&mut self, text: &str) {
+        for line in text.trim_end_matches('\n').split('\n') {
+            self.output.push_back(line.to_string());
+        }
+        while self.output.len() > Self::MAX_OUTPUT_LINES {
+            self.output.pop_front();
+        }
+    }
+    pub fn clear_output(&mut self) {
+        self.output.clear();
+        self.scroll = 0;
+    }
+    /// Shows `rule` before its first run.
+    pub fn watch(&mut self, rule: &str) {
+        let rule = self.rule(rule);
+        if rule.status.is_empty() {
+            rule.status = "watching".to_string();
+        }
+    }
+    pub fn start_run(&mut self, rule: &str, command: &str) {
+        let rule = self.rule(rule);
+        rule.command = command.to_string();
+        rule.status = "running".to_string();
+        rule.exit_code = None;
+        rule.duration = None;
+    }
+    pub fn finish_run(
+        &mut self,
+        rule: &str,
+        status: &str,
+        exit_code: Option<i32>,
+        duration: Duration,
+        is_success: bool,
+    ) {
+        let rule = self.rule(rule);
+        rule.status = status.to_string();
+        rule.exit_code = exit_code;
+        rule.duration = Some(duration);
+        rule.runs += 1;
+        if !is_success {
+            rule.failures += 1;
+        }
+    }
+    /// Positive lines scroll up to older output.
+    pub fn scroll(&mut self, lines: isize) {
+        // keep the first line in sight
+        let max_scroll = self.output.len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max_scroll);
+    }
+    pub fn set_farewell(&mut self, farewell: &str) {
+        self.farewell = Some(farewell.to_string());
+    }
+    fn rule(&mut self, name: &str) -> &mut RuleStatus {
+        match self.rules.iter().position(|rule| rule.name == name) {
+            Some(i) => &mut self.rules[i],
+            None => {
+                self.rules.push(RuleStatus {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                self.rules.last_mut().unwrap()
+            }
+        }
+    }
+    /// Output lines that fit `height`, `scroll` lines up from the end.
+    #[cfg(any(feature = "tui", test))]
+    fn visible_output(&self, height: usize) -> Vec<&str> {
+        let end = self.output.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(height);
+        self.output
+            .range(start..end)
+            .map(|line| line.as_str())
+            .collect()
+    }
+}
+
+#[cfg(feature = "tui")]
+impl Dashboard {
+    const HELP: &'static str =
+        " j/k scroll, r rerun, a run all, p pause, c clear, s summary, q quit ";
+    pub fn draw(&mut self) -> std::io::Result<()> {
+        use ratatui::{
+            crossterm::{cursor::Hide, execute, terminal::EnterAlternateScreen},
+            prelude::CrosstermBackend,
+            Terminal,
+        };
+        if self.terminal.is_none() {
+            execute!(std::io::stdout(), EnterAlternateScreen, Hide)?;
+            self.terminal = Some(Terminal::new(CrosstermBackend::new(std::io::stdout()))?);
+        }
+        let mut terminal = self.terminal.take().unwrap();
+        let drawn = terminal.draw(|frame| self.render(frame)).map(|_| ());
+        self.terminal = Some(terminal);
+        drawn
+    }
+    fn render(&self, frame: &mut ratatui::Frame) {
+        use ratatui::{
+            layout::{Constraint, Layout},
+            style::{Color, Modifier, Style},
+            widgets::{Block, List, Paragraph, Row, Table},
+        };
+
+        use super::stats::format_elapsed;
+        let [rules_area, body_area, help_area] = Layout::vertical([
+            Constraint::Length(self.rules.len().max(1) as u16 + 3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [events_area, output_area] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(body_area);
+
+        let rows = self.rules.iter().map(|rule| {
+            let color = match rule.status.as_str() {
+                "success" => Color::Green,
+                "running" => Color::Yellow,
+                "timeout" => Color::Magenta,
+                "watching" => Color::Reset,
+                _ => Color::Red,
+            };
+            Row::new(vec![
+                rule.name.clone(),
+                rule.command.clone(),
+                rule.status.clone(),
+                rule.exit_code
+                    .map(|code| code.to_string())
+                    .unwrap_or_default(),
+                rule.duration.map(format_elapsed).unwrap_or_default(),
+                format!("{}/{}", rule.failures, rule.runs),
+            ])
+            .style(Style::default().fg(color))
+        });
+        let rules = Table::new(
+            rows,
+            [
+                Constraint::Length(12),
+                Constraint::Min(20),
+                Constraint::Length(9),
+                Constraint::Length(5),
+                Constraint::Length(9),
+                Constraint::Length(9),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "rule", "command", "status", "exit", "duration", "failures",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(" rules "));
+        frame.render_widget(rules, rules_area);
+
+        let height = events_area.height.saturating_sub(2) as usize;
+        let skip = self.events.len().saturating_sub(height);
+        let events = List::new(self.events.iter().skip(skip).map(|event| event.as_str()))
+            .block(Block::bordered().title(" events "));
+        frame.render_widget(events, events_area);
+
+        let height = output_area.height.saturating_sub(2) as usize;
+        let title = if self.scroll == 0 {
+            " output ".to_string()
+        } else {
+            format!(" output (scrolled {} lines) ", self.scroll)
+        };
+        let output = Paragraph::new(self.visible_output(height).join("\n"))
+            .block(Block::bordered().title(title));
+        frame.render_widget(output, output_area);
+
+        frame.render_widget(
+            Paragraph::new(Self::HELP).style(Style::default().add_modifier(Modifier::DIM)),
+            help_area,
+        );
+    }
+}
+#[cfg(feature = "tui")]
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        use ratatui::crossterm::{cursor::Show, execute, terminal::LeaveAlternateScreen};
+        if self.terminal.take().is_some() {
+            let _ = execute!(std::io::stdout(), LeaveAlternateScreen, Show);
+        }
+        if let Some(farewell) = &self.farewell {
+            println!("{}", farewell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_dashboard {
+    use super::*;
+    #[test]
+    fn test_visible_output() {
+        let mut dashboard = Dashboard::new();
+        dashboard.push_output("1\n2\n3\n4\n");
+        assert_eq!(dashboard.visible_output(2), vec!["3", "4"]);
+        dashboard.scroll(1);
+        assert_eq!(dashboard.visible_output(2), vec!["2", "3"]);
+        dashboard.scroll(10);
+        assert_eq!(dashboard.visible_output(2), vec!["1"]);
+        dashboard.scroll(-10);
+        assert_eq!(dashboard.visible_output(2), vec!["3", "4"]);
+    }
+    #[test]
+    fn test_rule_status() {
+        let mut dashboard = Dashboard::new();
+        dashboard.start_run("tests", "cargo test");
+        dashboard.finish_run("tests", "error", Some(101), Duration::ZERO, false);
+        dashboard.start_run("tests", "cargo test");
+        assert_eq!(dashboard.rules.len(), 1);
+        assert_eq!(dashboard.rules[0].status, "running");
+        assert_eq!(dashboard.rules[0].failures, 1);
+    }
+}
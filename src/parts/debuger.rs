@@ -1,7 +1,111 @@
+use std::{cell::RefCell, io::Write, path::Path, time::Duration};
+
+use serde::Serialize;
+
 use crate::configs::debuger_config::MoniDebugerConfig;
 
+use super::{
+    color::{Color, ColorMode, MoniTheme},
+    dashboard::Dashboard,
+    executor::{ExecuteStatus, ExecutionResult, OutputChunk},
+    filestore::FileEvent,
+    sink::{MoniSink, StdoutSink},
+    stats::{format_elapsed, MoniStats},
+    template::MessageVars,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum MoniOutput {
+    /// lines made by MoniDebugerConfig
+    #[default]
+    Text,
+    /// one json object per line and event, for editor plugins and ci wrappers
+    Json,
+    /// a dashboard drawn on stdout, the sink is not used
+    Tui,
+}
+impl MoniOutput {
+    pub fn new(output: &str) -> Result<Self, String> {
+        match output {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            #[cfg(feature = "tui")]
+            "tui" => Ok(Self::Tui),
+            #[cfg(not(feature = "tui"))]
+            "tui" => Err("tui needs monirs built with the tui feature".to_string()),
+            _ => Err(format!("{} is not output. use text, json or tui", output)),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum MoniEvent<'e> {
+    Started,
+    FileChanged {
+        kind: &'e str,
+        path: &'e str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        root: Option<&'e str>,
+    },
+    CommandStarted {
+        command: &'e str,
+    },
+    CommandScheduled {
+        schedule: &'e str,
+        command: &'e str,
+    },
+    Output {
+        command: &'e str,
+        stream: &'e str,
+        chunk: &'e str,
+    },
+    CommandFinished {
+        command: &'e str,
+        status: &'e str,
+        exit_code: Option<i32>,
+        duration_ms: u64,
+    },
+    FunctionFinished {
+        path: &'e str,
+        status: &'e str,
+        output: &'e str,
+        duration_ms: u64,
+    },
+    Summary {
+        runs: usize,
+        failures: usize,
+        average_ms: u64,
+        max_ms: u64,
+        top_files: Vec<TriggerCount<'e>>,
+    },
+    Error {
+        message: &'e str,
+    },
+    Warning {
+        message: &'e str,
+    },
+    Info {
+        message: &'e str,
+    },
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct TriggerCount<'e> {
+    path: &'e str,
+    count: usize,
+}
+
 pub struct MoniDebuger<C: MoniDebugerConfig> {
     inner: C,
+    output: MoniOutput,
+    sink: RefCell<Box<dyn MoniSink>>,
+    theme: MoniTheme,
+    color: ColorMode,
+    clear_screen: bool,
+    clear_lines: usize,
+    vars: RefCell<MessageVars>,
+    dashboard: RefCell<Option<Dashboard>>,
 }
 impl<'a> Default for MoniDebuger<DefaultMoniDebugMessage<'a>> {
     fn default() -> Self {
@@ -12,25 +116,404 @@ impl<C> MoniDebuger<C>
 where
     C: MoniDebugerConfig,
 {
-    pub fn print_error_line(&self) -> () {
-        println!("{}", self.inner.error_message())
+    pub fn output(mut self, output: MoniOutput) -> Self {
+        self.set_output(output);
+        self
+    }
+    pub fn set_output(&mut self, output: MoniOutput) {
+        self.output = output;
+        let dashboard = (output == MoniOutput::Tui).then(Dashboard::new);
+        self.dashboard = RefCell::new(dashboard);
+    }
+    pub fn sink<S: MoniSink + 'static>(mut self, sink: S) -> Self {
+        self.set_sink(Box::new(sink));
+        self
+    }
+    pub fn set_sink(&mut self, sink: Box<dyn MoniSink>) {
+        self.sink = RefCell::new(sink);
+    }
+    pub fn color(mut self, color: ColorMode) -> Self {
+        self.color = color;
+        self
+    }
+    pub fn set_color(&mut self, color: ColorMode) {
+        self.color = color;
+    }
+    /// Clears the terminal before each execution. A sink that is not a terminal gets
+    /// `clear_lines` blank lines instead, so logs stay readable.
+    pub fn clear_screen(mut self, clear_screen: bool) -> Self {
+        self.clear_screen = clear_screen;
+        self
+    }
+    pub fn set_clear_screen(&mut self, clear_screen: bool) {
+        self.clear_screen = clear_screen;
+    }
+    pub fn clear_lines(mut self, clear_lines: usize) -> Self {
+        self.clear_lines = clear_lines;
+        self
+    }
+    pub fn set_clear_lines(&mut self, clear_lines: usize) {
+        self.clear_lines = clear_lines;
+    }
+    /// `{rule}` of the messages.
+    pub fn set_rule(&mut self, rule: &str) {
+        self.vars.get_mut().set_rule(rule);
+    }
+    /// Sets `{command}` and `{file}` and counts `{run_number}` for the following messages.
+    pub fn start_run(&self, execute_command: &str, filepath: Option<&str>) {
+        self.vars.borrow_mut().start_run(execute_command, filepath);
+        let rule = self.vars.borrow().rule_name().to_string();
+        self.with_dashboard(|dashboard| dashboard.start_run(&rule, execute_command));
+    }
+    /// Draws the dashboard of tui output. Other outputs are written as they happen.
+    pub fn refresh(&self) {
+        #[cfg(feature = "tui")]
+        if let Some(dashboard) = self.dashboard.borrow_mut().as_mut() {
+            // the terminal is gone, so there is nothing left to report it on
+            let _ = dashboard.draw();
+        }
+    }
+    /// Scrolls the output of the dashboard, positive `lines` to older output.
+    pub fn scroll(&self, lines: isize) {
+        self.with_dashboard(|dashboard| dashboard.scroll(lines));
+    }
+    pub fn print_clear_screen(&self) {
+        if self.clear_screen {
+            self.clear();
+        }
+    }
+    /// Clears even without `clear_screen`, e.g. when asked by a key.
+    pub fn clear(&self) {
+        self.with_dashboard(|dashboard| dashboard.clear_output());
+        if self.output != MoniOutput::Text {
+            return;
+        }
+        let mut sink = self.sink.borrow_mut();
+        let clear = if sink.is_terminal() {
+            // clear the screen and the scrollback, then move the cursor to the top
+            "\x1b[2J\x1b[3J\x1b[H".to_string()
+        } else {
+            "\n".repeat(self.clear_lines)
+        };
+        let _ = write!(sink, "{}", clear).and_then(|_| sink.flush());
+    }
+    pub fn print_error_line(&self) {
+        self.print_text(self.theme.error, || self.render(self.inner.error_message()))
+    }
+    pub fn print_timeout_line(&self) {
+        self.print_text(self.theme.timeout, || {
+            self.render(self.inner.timeout_message())
+        })
+    }
+    pub fn print_ok_line(&self) {
+        self.print_text(self.theme.success, || {
+            self.render(self.inner.success_message())
+        })
+    }
+    pub fn print_line(&self) {
+        self.print_text(self.theme.line, || self.render(self.inner.line_message()))
+    }
+    pub fn print_finished_line(&self, elapsed: Duration, exit_code: Option<i32>) {
+        self.print_text(self.theme.line, || {
+            self.render(
+                self.inner
+                    .finished_message(&format_elapsed(elapsed), exit_code),
+            )
+        })
+    }
+    pub fn print_start_line(&self) {
+        match self.output {
+            MoniOutput::Text => {
+                self.write_colored(self.theme.start, &self.render(self.inner.start_message()))
+            }
+            MoniOutput::Json => self.print_json(&MoniEvent::Started),
+            MoniOutput::Tui => {
+                let rule = self.vars.borrow().rule_name().to_string();
+                self.with_dashboard(|dashboard| dashboard.watch(&rule));
+                self.push_event("start monitaring");
+            }
+        }
+    }
+    pub fn print_execute_command_line(&self, execute_command: &str) {
+        match self.output {
+            MoniOutput::Text => self.write_colored(
+                self.theme.execute,
+                &self.render_around(self.inner.execute_message(execute_command), execute_command),
+            ),
+            MoniOutput::Json => self.print_json(&MoniEvent::CommandStarted {
+                command: execute_command,
+            }),
+            MoniOutput::Tui => {
+                self.push_event(&format!("execute {}", execute_command));
+                self.with_dashboard(|dashboard| {
+                    dashboard.push_output(&format!("$ {}", execute_command))
+                });
+            }
+        }
+    }
+    pub fn print_schedule_line(&self, schedule: &str, execute_command: &str) {
+        match self.output {
+            MoniOutput::Text => self.write_colored(
+                self.theme.schedule,
+                &self.render_around(
+                    self.inner.schedule_message(schedule, execute_command),
+                    execute_command,
+                ),
+            ),
+            MoniOutput::Json => self.print_json(&MoniEvent::CommandScheduled {
+                schedule,
+                command: execute_command,
+            }),
+            MoniOutput::Tui => self.push_event(&format!("{} {}", schedule, execute_command)),
+        }
+    }
+    /// `root` is the workspace the file is watched under.
+    pub fn print_file_event(&self, event: &FileEvent, root: Option<&Path>) {
+        let path = event.path().to_string_lossy();
+        let root = root.map(|root| root.to_string_lossy());
+        match self.output {
+            // created and modified files show up as the command they run
+            MoniOutput::Text => match event {
+                FileEvent::Removed(_) | FileEvent::DirRemoved(_) => {
+                    self.write_colored(self.theme.start, &format!(" removed {} ", path))
+                }
+                _ => {}
+            },
+            MoniOutput::Json => self.print_json(&MoniEvent::FileChanged {
+                kind: event.kind(),
+                path: &path,
+                root: root.as_deref(),
+            }),
+            MoniOutput::Tui => self.push_event(&format!("{} {}", event.kind(), path)),
+        }
+    }
+    /// Output while the command runs. Text output waits for the result instead.
+    pub fn print_output(&self, execute_command: &str, chunk: &OutputChunk) {
+        match self.output {
+            MoniOutput::Text => {}
+            MoniOutput::Json => self.print_json(&MoniEvent::Output {
+                command: execute_command,
+                stream: chunk.stream().as_str(),
+                chunk: chunk.text(),
+            }),
+            MoniOutput::Tui => self.with_dashboard(|dashboard| dashboard.push_output(chunk.text())),
+        }
+    }
+    pub fn print_execution_result(&self, result: &ExecutionResult) {
+        if result.status() != ExecuteStatus::Canceled {
+            self.vars.borrow_mut().finish_run(
+                result.command(),
+                result.exit_code(),
+                result.elapsed(),
+            );
+        }
+        if self.output == MoniOutput::Json {
+            self.print_json(&MoniEvent::CommandFinished {
+                command: result.command(),
+                status: result.status().as_str(),
+                exit_code: result.exit_code(),
+                duration_ms: result.elapsed().as_millis() as u64,
+            });
+            return;
+        }
+        if self.output == MoniOutput::Tui {
+            if result.status() == ExecuteStatus::Canceled {
+                self.push_event(&format!("restart {}", result.command()));
+                return;
+            }
+            let rule = self.vars.borrow().rule_name().to_string();
+            self.with_dashboard(|dashboard| {
+                dashboard.finish_run(
+                    &rule,
+                    result.status().as_str(),
+                    result.exit_code(),
+                    result.elapsed(),
+                    result.is_success(),
+                )
+            });
+            return;
+        }
+        match result.status() {
+            ExecuteStatus::Success => {
+                self.print_ok_line();
+                self.write_line(result.stdout());
+            }
+            ExecuteStatus::Error => {
+                self.print_error_line();
+                self.write_line(result.stderr());
+            }
+            ExecuteStatus::Timeout => {
+                self.print_timeout_line();
+                self.write_line(&format!("{}{}", result.stdout(), result.stderr()));
+            }
+            ExecuteStatus::Canceled => {
+                self.print_schedule_line("restart", result.command());
+                return;
+            }
+        }
+        self.print_finished_line(result.elapsed(), result.exit_code());
+        self.print_line();
+    }
+    pub fn print_fn_result(
+        &self,
+        filepath: &str,
+        result: &Result<String, String>,
+        elapsed: Duration,
+    ) {
+        self.vars.borrow_mut().finish_run(filepath, None, elapsed);
+        if self.output == MoniOutput::Json {
+            let (status, output) = match result {
+                Ok(output) => (ExecuteStatus::Success, output),
+                Err(output) => (ExecuteStatus::Error, output),
+            };
+            self.print_json(&MoniEvent::FunctionFinished {
+                path: filepath,
+                status: status.as_str(),
+                output,
+                duration_ms: elapsed.as_millis() as u64,
+            });
+            return;
+        }
+        if self.output == MoniOutput::Tui {
+            let rule = self.vars.borrow().rule_name().to_string();
+            let (status, output) = match result {
+                Ok(output) => (ExecuteStatus::Success, output),
+                Err(output) => (ExecuteStatus::Error, output),
+            };
+            self.with_dashboard(|dashboard| {
+                dashboard.push_output(output);
+                dashboard.finish_run(&rule, status.as_str(), None, elapsed, result.is_ok());
+            });
+            return;
+        }
+        match result {
+            Ok(output) => {
+                self.print_ok_line();
+                self.write_line(output);
+            }
+            Err(e) => {
+                self.print_error_line();
+                self.write_line(e);
+            }
+        }
+        self.print_finished_line(elapsed, None);
+        self.print_line();
+    }
+    pub fn print_summary(&self, stats: &MoniStats) {
+        let top_triggers = stats.top_triggers(Self::SUMMARY_TOP_FILES);
+        if self.output == MoniOutput::Json {
+            self.print_json(&MoniEvent::Summary {
+                runs: stats.runs(),
+                failures: stats.failures(),
+                average_ms: stats.average_elapsed().as_millis() as u64,
+                max_ms: stats.max_elapsed().as_millis() as u64,
+                top_files: top_triggers
+                    .into_iter()
+                    .map(|(path, count)| TriggerCount { path, count })
+                    .collect(),
+            });
+            return;
+        }
+        let mut summary = format!(
+            "runs {}, failures {}, average {}, max {}",
+            stats.runs(),
+            stats.failures(),
+            format_elapsed(stats.average_elapsed()),
+            format_elapsed(stats.max_elapsed()),
+        );
+        for (path, count) in top_triggers {
+            summary.push_str(&format!("\n{:>5} {}", count, path));
+        }
+        if self.output == MoniOutput::Tui {
+            // the last summary is for after the dashboard is closed
+            self.with_dashboard(|dashboard| dashboard.set_farewell(&summary));
+            self.push_event(summary.lines().next().unwrap_or_default());
+            return;
+        }
+        self.write_colored(self.theme.start, &summary);
+    }
+    /// Errors of moni itself, e.g. a command that can not be spawned.
+    pub fn print_error_message(&self, message: &str) {
+        match self.output {
+            MoniOutput::Text => self.write_colored(self.theme.error, message),
+            MoniOutput::Json => self.print_json(&MoniEvent::Error { message }),
+            MoniOutput::Tui => self.push_event(&format!("error {}", message.trim())),
+        }
+    }
+    /// Something moni keeps going with but the person watching should fix,
+    /// e.g. a workspace with more files than max_files.
+    pub fn print_warning_message(&self, message: &str) {
+        match self.output {
+            MoniOutput::Text => {
+                self.write_colored(self.theme.warning, &format!(" warning: {} ", message))
+            }
+            MoniOutput::Json => self.print_json(&MoniEvent::Warning { message }),
+            MoniOutput::Tui => self.push_event(&format!("warning {}", message)),
+        }
+    }
+    /// Notes for the person watching, e.g. that monitaring is paused.
+    pub fn print_info_message(&self, message: &str) {
+        match self.output {
+            MoniOutput::Text => self.write_colored(self.theme.start, message),
+            MoniOutput::Json => self.print_json(&MoniEvent::Info { message }),
+            MoniOutput::Tui => self.push_event(message),
+        }
     }
-    pub fn print_ok_line(&self) -> () {
-        println!("{}", self.inner.success_message())
+    fn with_dashboard<F: FnOnce(&mut Dashboard)>(&self, f: F) {
+        if let Some(dashboard) = self.dashboard.borrow_mut().as_mut() {
+            f(dashboard)
+        }
+    }
+    fn push_event(&self, event: &str) {
+        let vars = self.vars.borrow();
+        let event = format!("{} {}", vars.render("{time}"), event.trim());
+        self.with_dashboard(|dashboard| dashboard.push_event(&event));
     }
-    pub fn print_line(&self) -> () {
-        println!("{}", self.inner.line_message())
+    fn render(&self, message: String) -> String {
+        self.vars.borrow().render(&message)
     }
-    pub fn print_start_line(&self) -> () {
-        println!("{}", self.inner.start_message())
+    /// The command is put in the message by the config, so it is not rendered again.
+    fn render_around(&self, message: String, execute_command: &str) -> String {
+        self.vars.borrow().render_around(&message, execute_command)
     }
-    pub fn print_execute_command_line(&self, execute_command: &str) -> () {
-        println!("{}", self.inner.execute_message(execute_command))
+    fn print_text<F: FnOnce() -> String>(&self, color: Color, message: F) {
+        if self.output == MoniOutput::Text {
+            self.write_colored(color, &message())
+        }
+    }
+    fn write_colored(&self, color: Color, line: &str) {
+        if self.color.is_color(self.sink.borrow().is_terminal()) {
+            self.write_line(&color.paint(line))
+        } else {
+            self.write_line(line)
+        }
+    }
+    fn print_json(&self, event: &MoniEvent) {
+        self.write_line(&serde_json::to_string(event).unwrap())
+    }
+    fn write_line(&self, line: &str) {
+        let mut sink = self.sink.borrow_mut();
+        // there is nowhere left to report a broken sink, so keep monitaring
+        let _ = writeln!(sink, "{}", line).and_then(|_| sink.flush());
     }
 }
-impl<'a, C: MoniDebugerConfig> From<C> for MoniDebuger<C> {
+impl<C: MoniDebugerConfig> MoniDebuger<C> {
+    const DEFAULT_CLEAR_LINES: usize = 3;
+    const SUMMARY_TOP_FILES: usize = 5;
+}
+impl<C: MoniDebugerConfig> From<C> for MoniDebuger<C> {
     fn from(config: C) -> Self {
-        Self { inner: config }
+        Self {
+            theme: config.theme(),
+            color: config.color_mode(),
+            inner: config,
+            output: MoniOutput::default(),
+            sink: RefCell::new(Box::new(StdoutSink)),
+            clear_screen: false,
+            clear_lines: Self::DEFAULT_CLEAR_LINES,
+            vars: RefCell::new(MessageVars::new()),
+            dashboard: RefCell::new(None),
+        }
     }
 }
 
@@ -43,12 +526,21 @@ impl<'a> MoniDebugerConfig for DefaultMoniDebugMessage<'a> {
     fn error_message(&self) -> String {
         self.make_error_line_message()
     }
+    fn timeout_message(&self) -> String {
+        self.make_timeout_line_message()
+    }
     fn execute_message(&self, command: &str) -> String {
         self.make_execute_command_line_message(command)
     }
+    fn schedule_message(&self, schedule: &str, command: &str) -> String {
+        self.make_schedule_line_message(schedule, command)
+    }
     fn line_message(&self) -> String {
         self.make_line_message()
     }
+    fn finished_message(&self, elapsed: &str, exit_code: Option<i32>) -> String {
+        self.make_finished_line_message(elapsed, exit_code)
+    }
     fn start_message(&self) -> String {
         self.make_start_line_message()
     }
@@ -67,15 +559,14 @@ impl Default for DefaultMoniDebugMessage<'static> {
 }
 impl<'a> DefaultMoniDebugMessage<'a> {
     fn calc_added_separator_len(&self, message: &str) -> usize {
-        let diff = self.title.len() - message.len();
-        let added_separator_len = self.separator_len + (diff / 2);
-        added_separator_len
+        let diff = self.title.len().saturating_sub(message.len());
+        self.separator_len + (diff / 2)
     }
     fn make_message(&self, message: &str) -> String {
         let separator_len = self.calc_added_separator_len(message);
         let top_and_bottom = self.separator.repeat(separator_len);
-        let diff = self.title.len() - message.len();
-        if diff % 2 != 0 {
+        let diff = self.title.len().saturating_sub(message.len());
+        if !diff.is_multiple_of(2) {
             return format!("{}{}{}-", top_and_bottom, message, top_and_bottom,);
         }
         format!("{}{}{}", top_and_bottom, message, top_and_bottom,)
@@ -93,6 +584,9 @@ impl<'a> DefaultMoniDebugMessage<'a> {
     pub fn make_execute_command_line_message(&self, command: &str) -> String {
         self.make_message(&format!(" execute {}", command))
     }
+    pub fn make_schedule_line_message(&self, schedule: &str, command: &str) -> String {
+        self.make_message(&format!(" {} {}", schedule, command))
+    }
     pub fn make_line_message(&self) -> String {
         let message = "--";
         self.make_message(message)
@@ -105,6 +599,16 @@ impl<'a> DefaultMoniDebugMessage<'a> {
         let message = " error ";
         self.make_message(message)
     }
+    pub fn make_timeout_line_message(&self) -> String {
+        let message = " timeout ";
+        self.make_message(message)
+    }
+    pub fn make_finished_line_message(&self, elapsed: &str, exit_code: Option<i32>) -> String {
+        match exit_code {
+            Some(exit_code) => self.make_message(&format!(" {} exit {} ", elapsed, exit_code)),
+            None => self.make_message(&format!(" {} ", elapsed)),
+        }
+    }
     pub fn make_execute_line_message(&self) -> String {
         let message = " execute ";
         format!("\n{}\n", self.make_message(message),)
@@ -114,6 +618,7 @@ impl<'a> DefaultMoniDebugMessage<'a> {
 #[cfg(test)]
 mod test_debuger {
     use super::*;
+    use crate::{configs::json::MoniDebugerConfigJson, parts::sink::BufferSink};
     #[test]
     fn test_line_len() {
         let default_debuger = DefaultMoniDebugMessage::default();
@@ -125,5 +630,186 @@ mod test_debuger {
             default_debuger.success_message().len(),
             default_debuger.line_message().len()
         );
+        assert_eq!(
+            default_debuger.timeout_message().len(),
+            default_debuger.line_message().len()
+        );
+    }
+    #[test]
+    fn test_json_event() {
+        let event = MoniEvent::CommandFinished {
+            command: "cargo test",
+            status: ExecuteStatus::Error.as_str(),
+            exit_code: Some(101),
+            duration_ms: 1200,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"command_finished","command":"cargo test","status":"error","exit_code":101,"duration_ms":1200}"#
+        );
+        let event = MoniEvent::FileChanged {
+            kind: "modified",
+            path: "./src/lib.rs",
+            root: Some("./src"),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"file_changed","kind":"modified","path":"./src/lib.rs","root":"./src"}"#
+        );
+    }
+    #[test]
+    fn test_print_to_sink() {
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default()
+            .output(MoniOutput::Json)
+            .sink(buffer.clone());
+        debuger.print_start_line();
+        debuger.print_error_message("oops");
+        assert_eq!(
+            buffer.contents(),
+            "{\"event\":\"started\"}\n{\"event\":\"error\",\"message\":\"oops\"}\n"
+        );
+    }
+    #[test]
+    fn test_print_colored() {
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default()
+            .color(ColorMode::Always)
+            .sink(buffer.clone());
+        debuger.print_ok_line();
+        assert_eq!(
+            buffer.contents(),
+            format!(
+                "{}\n",
+                Color::Green.paint(&DefaultMoniDebugMessage::default().success_message())
+            )
+        );
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default()
+            .color(ColorMode::Always)
+            .sink(buffer.clone());
+        debuger.print_warning_message("too many files");
+        assert_eq!(
+            buffer.contents(),
+            format!("{}\n", Color::Yellow.paint(" warning: too many files "))
+        );
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default().sink(buffer.clone());
+        debuger.print_ok_line();
+        assert!(!buffer.contents().contains('\x1b'));
+    }
+    #[test]
+    fn test_print_clear_screen_case_not_terminal() {
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default()
+            .clear_screen(true)
+            .clear_lines(2)
+            .sink(buffer.clone());
+        debuger.print_clear_screen();
+        assert_eq!(buffer.contents(), "\n\n");
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default().sink(buffer.clone());
+        debuger.print_clear_screen();
+        assert_eq!(buffer.contents(), "");
+    }
+    #[test]
+    fn test_print_summary() {
+        let mut stats = MoniStats::new();
+        stats.record_trigger("./src/lib.rs");
+        stats.record(&ExecutionResult::from_fn_result(
+            "./src/lib.rs",
+            &Err("error".to_string()),
+            Duration::from_millis(1500),
+        ));
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default().sink(buffer.clone());
+        debuger.print_summary(&stats);
+        assert_eq!(
+            buffer.contents(),
+            "runs 1, failures 1, average 1.50s, max 1.50s\n    1 ./src/lib.rs\n"
+        );
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default()
+            .output(MoniOutput::Json)
+            .sink(buffer.clone());
+        debuger.print_summary(&stats);
+        assert_eq!(
+            buffer.contents(),
+            "{\"event\":\"summary\",\"runs\":1,\"failures\":1,\"average_ms\":1500,\"max_ms\":1500,\"top_files\":[{\"path\":\"./src/lib.rs\",\"count\":1}]}\n"
+        );
+    }
+    #[test]
+    fn test_print_template() {
+        let buffer = BufferSink::new();
+        let mut debuger = MoniDebuger::from(TemplateMessage);
+        debuger.set_rule("tests");
+        let debuger = debuger.sink(buffer.clone());
+        debuger.start_run("cargo test", Some("./src/lib.rs"));
+        debuger.print_execute_command_line("cargo test");
+        debuger.print_execution_result(&ExecutionResult::from_fn_result(
+            "cargo test",
+            &Err(String::new()),
+            Duration::from_millis(20),
+        ));
+        assert_eq!(
+            buffer.contents(),
+            "[tests] #1 cargo test ./src/lib.rs\nerror  in 20ms\n\n\n20ms\n"
+        );
+    }
+    #[test]
+    fn test_print_command_with_template_variables() {
+        let command = "echo {file} {time} {command} ${HOME}";
+        let json = serde_json::from_str::<MoniDebugerConfigJson>(
+            r#"{"execute": " {run_number} {command} on {file} ", "schedule": " {schedule} {command} "}"#,
+        )
+        .unwrap();
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::from(json).sink(buffer.clone());
+        debuger.start_run(command, Some("a.rs"));
+        debuger.print_execute_command_line(command);
+        debuger.print_schedule_line("queue", command);
+        assert_eq!(
+            buffer.contents(),
+            format!(" 1 {} on a.rs \n queue {} \n", command, command)
+        );
+        let buffer = BufferSink::new();
+        let debuger = MoniDebuger::default().sink(buffer.clone());
+        debuger.start_run(command, Some("a.rs"));
+        debuger.print_execute_command_line(command);
+        assert!(buffer.contents().contains(&format!(" execute {}", command)));
+    }
+    struct TemplateMessage;
+    impl MoniDebugerConfig for TemplateMessage {
+        fn start_message(&self) -> String {
+            String::new()
+        }
+        fn success_message(&self) -> String {
+            String::new()
+        }
+        fn error_message(&self) -> String {
+            "error {exit_code} in {duration}".to_string()
+        }
+        fn timeout_message(&self) -> String {
+            String::new()
+        }
+        fn execute_message(&self, _: &str) -> String {
+            "[{rule}] #{run_number} {command} {file}".to_string()
+        }
+        fn schedule_message(&self, _: &str, _: &str) -> String {
+            String::new()
+        }
+        fn line_message(&self) -> String {
+            "{duration}".to_string()
+        }
+        fn finished_message(&self, _: &str, _: Option<i32>) -> String {
+            String::new()
+        }
+    }
+    #[test]
+    fn test_message_longer_than_title() {
+        let default_debuger = DefaultMoniDebugMessage::default();
+        let message =
+            default_debuger.schedule_message("queue", "cargo test --workspace --all-targets");
+        assert!(message.contains(" queue cargo test --workspace --all-targets"));
     }
 }
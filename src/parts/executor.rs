@@ -0,0 +1,397 @@
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExecuteStatus {
+    Success,
+    Error,
+    Timeout,
+    Canceled,
+}
+impl ExecuteStatus {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Success => "success",
+            Self::Error => "error",
+            Self::Timeout => "timeout",
+            Self::Canceled => "canceled",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+impl OutputStream {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+        }
+    }
+}
+
+/// A line (with its line break) the command wrote while running.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OutputChunk {
+    stream: OutputStream,
+    text: String,
+}
+impl OutputChunk {
+    pub fn stream(&self) -> OutputStream {
+        self.stream
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ExecutionResult {
+    command: String,
+    status: ExecuteStatus,
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    elapsed: Duration,
+}
+impl ExecutionResult {
+    /// Result of `exe_fn`, which has no command, so the file path stands in for it.
+    pub fn from_fn_result(
+        filepath: &str,
+        result: &Result<String, String>,
+        elapsed: Duration,
+    ) -> Self {
+        let (status, stdout, stderr) = match result {
+            Ok(output) => (ExecuteStatus::Success, output.clone(), String::new()),
+            Err(e) => (ExecuteStatus::Error, String::new(), e.clone()),
+        };
+        Self {
+            command: filepath.to_string(),
+            status,
+            exit_code: None,
+            stdout,
+            stderr,
+            elapsed,
+        }
+    }
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+    pub fn status(&self) -> ExecuteStatus {
+        self.status
+    }
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+    pub fn stdout(&self) -> &str {
+        &self.stdout
+    }
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    pub fn is_success(&self) -> bool {
+        self.status == ExecuteStatus::Success
+    }
+}
+
+/// A shell command spawned in its own process group.
+///
+/// The command is polled instead of waited on, so the caller decides how long to block.
+/// When `timeout` is exceeded, the whole process group is killed, which also stops
+/// anything the command itself spawned (e.g. `cargo test` running test binaries).
+pub struct RunningCommand {
+    command: String,
+    child: Child,
+    started_at: Instant,
+    timeout: Option<Duration>,
+    readers: Vec<JoinHandle<()>>,
+    output: Receiver<OutputChunk>,
+    unread: Vec<OutputChunk>,
+    stdout: String,
+    stderr: String,
+}
+impl RunningCommand {
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+    /// How long the output of a finished command is waited for. A background process
+    /// the command started (e.g. `server &`) keeps the pipes open, and the watch loop
+    /// must not wait for it.
+    const OUTPUT_GRACE: Duration = Duration::from_millis(100);
+    pub fn spawn(command: &str, timeout: Option<Duration>) -> io::Result<Self> {
+        let mut shell = shell_command(command);
+        // a background process group reading the terminal would be stopped, and moni reads keys
+        shell
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        shell.process_group(0);
+        let mut child = shell.spawn()?;
+        let (sender, output) = mpsc::channel();
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(spawn_reader(stdout, OutputStream::Stdout, sender.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(spawn_reader(stderr, OutputStream::Stderr, sender));
+        }
+        Ok(Self {
+            command: command.to_string(),
+            child,
+            started_at: Instant::now(),
+            timeout,
+            readers,
+            output,
+            unread: Vec::new(),
+            stdout: String::new(),
+            stderr: String::new(),
+        })
+    }
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+    pub fn is_timeout(&self) -> bool {
+        match self.timeout {
+            Some(timeout) => self.started_at.elapsed() >= timeout,
+            None => false,
+        }
+    }
+    /// Output written since the last call. The result still contains the whole output.
+    pub fn take_output(&mut self) -> Vec<OutputChunk> {
+        self.receive_output();
+        std::mem::take(&mut self.unread)
+    }
+    /// Returns the result if the command has finished or timed out, otherwise `None`.
+    pub fn try_finish(&mut self) -> io::Result<Option<ExecutionResult>> {
+        if let Some(status) = self.child.try_wait()? {
+            return Ok(Some(
+                self.collect(status_to_execute_status(&status), status.code()),
+            ));
+        }
+        if self.is_timeout() {
+            self.kill_process_group()?;
+            return Ok(Some(self.collect(ExecuteStatus::Timeout, None)));
+        }
+        Ok(None)
+    }
+    /// Kills the process group before it finishes, e.g. because a newer change restarts it.
+    pub fn cancel(mut self) -> io::Result<ExecutionResult> {
+        if let Some(result) = self.try_finish()? {
+            return Ok(result);
+        }
+        self.kill_process_group()?;
+        Ok(self.collect(ExecuteStatus::Canceled, None))
+    }
+    pub fn wait(mut self) -> io::Result<ExecutionResult> {
+        loop {
+            if let Some(result) = self.try_finish()? {
+                return Ok(result);
+            }
+            thread::sleep(Self::POLL_INTERVAL);
+        }
+    }
+    /// Only called after `try_wait` returned `None`. Until the child is reaped its pid,
+    /// and so its group id, can not be reused by another process.
+    #[cfg(unix)]
+    fn kill_process_group(&mut self) -> io::Result<()> {
+        // the child is the leader of its own group, so the group id is the child pid
+        let pgid = self.child.id() as libc::pid_t;
+        if unsafe { libc::kill(-pgid, libc::SIGKILL) } != 0 {
+            let e = io::Error::last_os_error();
+            // the whole group has already exited, which is what killing it is for
+            if e.raw_os_error() != Some(libc::ESRCH) {
+                return Err(e);
+            }
+        }
+        self.child.wait()?;
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    fn kill_process_group(&mut self) -> io::Result<()> {
+        self.child.kill()?;
+        self.child.wait()?;
+        Ok(())
+    }
+    fn receive_output(&mut self) {
+        while let Ok(chunk) = self.output.try_recv() {
+            match chunk.stream {
+                OutputStream::Stdout => self.stdout.push_str(&chunk.text),
+                OutputStream::Stderr => self.stderr.push_str(&chunk.text),
+            }
+            self.unread.push(chunk);
+        }
+    }
+    fn collect(&mut self, status: ExecuteStatus, exit_code: Option<i32>) -> ExecutionResult {
+        // readers end when the pipes close, so after joining every chunk is in the channel.
+        // readers still running after the grace period are left behind, and stop once
+        // the pipes close or this command is dropped
+        let deadline = Instant::now() + Self::OUTPUT_GRACE;
+        while self.readers.iter().any(|reader| !reader.is_finished()) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(1));
+        }
+        for reader in self.readers.drain(..) {
+            if reader.is_finished() {
+                let _ = reader.join();
+            }
+        }
+        self.receive_output();
+        ExecutionResult {
+            command: self.command.clone(),
+            status,
+            exit_code,
+            stdout: self.stdout.clone(),
+            stderr: self.stderr.clone(),
+            elapsed: self.started_at.elapsed(),
+        }
+    }
+}
+
+fn status_to_execute_status(status: &ExitStatus) -> ExecuteStatus {
+    if status.success() {
+        ExecuteStatus::Success
+    } else {
+        ExecuteStatus::Error
+    }
+}
+fn spawn_reader<R: Read + Send + 'static>(
+    source: R,
+    stream: OutputStream,
+    sender: Sender<OutputChunk>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut buf = Vec::new();
+        while let Ok(len) = reader.read_until(b'\n', &mut buf) {
+            if len == 0 {
+                break;
+            }
+            let text = String::from_utf8_lossy(&buf).to_string();
+            if sender.send(OutputChunk { stream, text }).is_err() {
+                break;
+            }
+            buf.clear();
+        }
+    })
+}
+
+pub(crate) fn shell_command(command: &str) -> Command {
+    let mut shell = target_os_command();
+    shell.arg("-c").arg(command);
+    shell
+}
+#[cfg(target_os = "linux")]
+fn target_os_command() -> Command {
+    Command::new("bash")
+}
+#[cfg(target_os = "windows")]
+fn target_os_command() -> Command {
+    Command::new("bash")
+}
+#[cfg(target_os = "macos")]
+fn target_os_command() -> Command {
+    Command::new("zsh")
+}
+
+#[cfg(test)]
+mod test_executor {
+    use super::*;
+    #[test]
+    fn test_wait_case_success() {
+        let result = RunningCommand::spawn("echo hello", None)
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert_eq!(result.status(), ExecuteStatus::Success);
+        assert_eq!(result.exit_code(), Some(0));
+        assert_eq!(result.stdout(), "hello\n");
+    }
+    #[test]
+    fn test_wait_case_error() {
+        let result = RunningCommand::spawn("echo oops >&2; exit 3", None)
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert_eq!(result.status(), ExecuteStatus::Error);
+        assert_eq!(result.exit_code(), Some(3));
+        assert_eq!(result.stderr(), "oops\n");
+    }
+    #[test]
+    fn test_wait_case_timeout_kills_process_group() {
+        let started_at = Instant::now();
+        // the background sleep would keep the stdout pipe open if only the shell was killed
+        let result = RunningCommand::spawn("sleep 10 & sleep 10", Some(Duration::from_millis(200)))
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert_eq!(result.status(), ExecuteStatus::Timeout);
+        assert_eq!(result.exit_code(), None);
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+    }
+    #[test]
+    fn test_take_output() {
+        let mut running = RunningCommand::spawn("echo 1; echo 2 >&2", None).unwrap();
+        let mut chunks = Vec::new();
+        while running.try_finish().unwrap().is_none() {
+            chunks.append(&mut running.take_output());
+        }
+        chunks.append(&mut running.take_output());
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.contains(&OutputChunk {
+            stream: OutputStream::Stdout,
+            text: "1\n".to_string()
+        }));
+        assert!(chunks.contains(&OutputChunk {
+            stream: OutputStream::Stderr,
+            text: "2\n".to_string()
+        }));
+        assert!(running.take_output().is_empty());
+    }
+    #[test]
+    fn test_wait_case_background_process_keeps_pipes() {
+        let started_at = Instant::now();
+        let result = RunningCommand::spawn("echo started; sleep 5 &", None)
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert_eq!(result.status(), ExecuteStatus::Success);
+        assert_eq!(result.stdout(), "started\n");
+        assert!(started_at.elapsed() < Duration::from_secs(2));
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_kill_process_group_case_leader_alive() {
+        let mut running = RunningCommand::spawn("sleep 10 & sleep 10", None).unwrap();
+        assert!(running.child.try_wait().unwrap().is_none());
+        running.kill_process_group().unwrap();
+        // the background sleep holds the pipes open until the whole group is killed
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while running.readers.iter().any(|reader| !reader.is_finished())
+            && Instant::now() < deadline
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(running.readers.iter().all(|reader| reader.is_finished()));
+        let result = running.collect(ExecuteStatus::Canceled, None);
+        assert_eq!(result.status(), ExecuteStatus::Canceled);
+    }
+    #[test]
+    fn test_cancel() {
+        let running = RunningCommand::spawn("sleep 10", None).unwrap();
+        let result = running.cancel().unwrap();
+        assert_eq!(result.status(), ExecuteStatus::Canceled);
+        assert_eq!(result.command(), "sleep 10");
+    }
+}
@@ -1,96 +1,132 @@
-use std::path::PathBuf;
+use std::{borrow::Cow, path::Path};
 
+/// Any file extension, including multi-part ones like `tar.gz` or `d.ts`.
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub enum Extension {
-    Txt,
-    Csv,
-    Xlsx,
-    Xlsm,
-    Pptx,
-    Bat,
-    Java,
-    Class,
-    Json,
-    Py,
-    Rs,
-    Ts,
-    Js,
-    Tsx,
-    Jsx,
-    Md,
-    Other,
+pub struct Extension {
+    extension: Cow<'static, str>,
+    case_insensitive: bool,
 }
 impl Extension {
-    pub fn new(path: &PathBuf) -> Result<Self, String> {
-        if let Some(Some(extension)) = path.extension().map(|path| path.to_str()) {
-            Ok(Self::str_to_self(extension))
-        } else {
-            Err(format!("{:#?} has not extension", path))
+    pub const TXT: Self = Self::known("txt");
+    pub const CSV: Self = Self::known("csv");
+    pub const XLSX: Self = Self::known("xlsx");
+    pub const XLSM: Self = Self::known("xlsm");
+    pub const PPTX: Self = Self::known("pptx");
+    pub const BAT: Self = Self::known("bat");
+    pub const JAVA: Self = Self::known("java");
+    pub const CLASS: Self = Self::known("class");
+    pub const JSON: Self = Self::known("json");
+    pub const PY: Self = Self::known("py");
+    pub const RS: Self = Self::known("rs");
+    pub const TS: Self = Self::known("ts");
+    pub const JS: Self = Self::known("js");
+    pub const TSX: Self = Self::known("tsx");
+    pub const JSX: Self = Self::known("jsx");
+    pub const MD: Self = Self::known("md");
+    pub const TOML: Self = Self::known("toml");
+    pub const YAML: Self = Self::known("yaml");
+    pub const YML: Self = Self::known("yml");
+    pub const MJS: Self = Self::known("mjs");
+    pub const CJS: Self = Self::known("cjs");
+    pub const HTML: Self = Self::known("html");
+    pub const CSS: Self = Self::known("css");
+    pub const SCSS: Self = Self::known("scss");
+    pub const VUE: Self = Self::known("vue");
+    pub const SVELTE: Self = Self::known("svelte");
+    pub const XML: Self = Self::known("xml");
+    pub const GRADLE: Self = Self::known("gradle");
+    pub const KT: Self = Self::known("kt");
+    pub const PROPERTIES: Self = Self::known("properties");
+    pub const CFG: Self = Self::known("cfg");
+    pub const RST: Self = Self::known("rst");
+    pub const ADOC: Self = Self::known("adoc");
+    const fn known(extension: &'static str) -> Self {
+        Self {
+            extension: Cow::Borrowed(extension),
+            case_insensitive: false,
         }
     }
-    pub fn is_match(&self, path: &PathBuf) -> bool {
-        if let Some(Some(extension)) = path.extension().map(|path| path.to_str()) {
-            self.as_str() == extension
+    /// The last extension of `path`, e.g. `gz` of `archive.tar.gz`.
+    pub fn new(path: &Path) -> Result<Self, String> {
+        if let Some(extension) = path.extension() {
+            Ok(Self::from(extension.to_string_lossy().as_ref()))
         } else {
-            false
+            Err(format!("{:#?} has not extension", path))
         }
     }
-    fn str_to_self(extension: &str) -> Self {
-        match extension {
-            "txt" => Self::Txt,
-            "csv" => Self::Csv,
-            "xlsx" => Self::Xlsx,
-            "xlsm" => Self::Xlsm,
-            "pptx" => Self::Pptx,
-            "bat" => Self::Bat,
-            "java" => Self::Java,
-            "class" => Self::Class,
-            "json" => Self::Json,
-            "py" => Self::Py,
-            "rs" => Self::Rs,
-            "ts" => Self::Ts,
-            "js" => Self::Js,
-            "tsx" => Self::Tsx,
-            "jsx" => Self::Jsx,
-            "md" => Self::Md,
-            _ => Self::Other,
-        }
+    /// Matches `RS`, `Rs` and `rs` alike.
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
     }
-    fn as_str(&self) -> &'static str {
-        match *self {
-            Self::Txt => "txt",
-            Self::Csv => "csv",
-            Self::Xlsx => "xlsx",
-            Self::Xlsm => "xlsm",
-            Self::Pptx => "pptx",
-            Self::Bat => "bat",
-            Self::Java => "java",
-            Self::Class => "class",
-            Self::Json => "json",
-            Self::Py => "py",
-            Self::Rs => "rs",
-            Self::Ts => "ts",
-            Self::Js => "js",
-            Self::Tsx => "tsx",
-            Self::Jsx => "jsx",
-            Self::Md => "md",
-            Self::Other => "other",
-        }
+    pub fn as_str(&self) -> &str {
+        &self.extension
+    }
+    /// Whether the file name ends with `.extension` after a non-empty stem, so `gz`
+    /// and `tar.gz` match `a.tar.gz`, but `gitignore` does not match `.gitignore`.
+    pub fn is_match(&self, path: &Path) -> bool {
+        let Some(filename) = path.file_name() else {
+            return false;
+        };
+        let filename = filename.to_string_lossy();
+        let Some((stem, extension)) = filename
+            .len()
+            .checked_sub(self.extension.len())
+            .filter(|&at| filename.is_char_boundary(at))
+            .map(|at| filename.split_at(at))
+        else {
+            return false;
+        };
+        let is_same = if self.case_insensitive {
+            extension.eq_ignore_ascii_case(&self.extension)
+        } else {
+            extension == self.extension
+        };
+        is_same && stem.strip_suffix('.').is_some_and(|stem| !stem.is_empty())
     }
 }
 
+/// Leading dots are optional, so `rs` and `.rs` are the same extension.
 impl From<&str> for Extension {
     fn from(extension: &str) -> Self {
-        Self::str_to_self(extension)
+        Self {
+            extension: Cow::Owned(extension.trim_start_matches('.').to_string()),
+            case_insensitive: false,
+        }
     }
 }
 #[cfg(test)]
 mod extension_test {
+    use std::path::PathBuf;
+
     use super::*;
     #[test]
     fn test_case_rs() {
         let extension = Extension::new(&PathBuf::from("test/test.rs")).unwrap();
+        assert_eq!(extension, Extension::RS);
         assert!(extension.is_match(&PathBuf::from("test/test/test.rs")));
         assert!(!extension.is_match(&PathBuf::from("test/test/test.py")));
     }
+    #[test]
+    fn test_case_unknown() {
+        assert!(Extension::from("go").is_match(&PathBuf::from("main.go")));
+        assert!(!Extension::from("toml").is_match(&PathBuf::from("main.go")));
+        assert!(!Extension::from("gitignore").is_match(&PathBuf::from(".gitignore")));
+    }
+    #[test]
+    fn test_case_multi_part() {
+        let extension = Extension::from(".tar.gz");
+        assert!(extension.is_match(&PathBuf::from("dist/app.tar.gz")));
+        assert!(!extension.is_match(&PathBuf::from("dist/app.gz")));
+        assert!(Extension::from("gz").is_match(&PathBuf::from("dist/app.tar.gz")));
+        assert!(!Extension::from("ts").is_match(&PathBuf::from("src/app.tsx")));
+    }
+    #[test]
+    fn test_case_insensitive() {
+        let path = PathBuf::from("photo.JPG");
+        assert!(!Extension::from("jpg").is_match(&path));
+        assert!(Extension::from("jpg")
+            .case_insensitive(true)
+            .is_match(&path));
+    }
 }
@@ -1,51 +1,154 @@
 use std::{
+    collections::{BinaryHeap, HashSet},
+    ffi::OsStr,
     fs::{self},
-    path::PathBuf,
-    rc::Rc,
+    io::ErrorKind,
+    mem,
+    path::{Component, Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
 };
 
-use regex::Regex;
+use rayon::prelude::*;
 
-use super::extensions::Extension;
+use super::{extensions::Extension, pattern::PathPattern, presets::Preset};
 
+#[derive(Clone)]
 pub struct FileSearcherBuilder<'a> {
-    root: &'a str,
+    root: &'a Path,
     target_extension: Vec<Extension>,
-    ignore_re: Vec<Regex>,
+    ignore_re: Vec<PathPattern>,
+    include_re: Vec<PathPattern>,
     ignore_filenames: Vec<&'a str>,
     ignore_extension: Vec<Extension>,
+    case_insensitive_extensions: bool,
+    follow_symlinks: bool,
+    watch_dirs: bool,
+    dir_include_re: Vec<PathPattern>,
+    limits: SearchLimits,
+}
+impl<'a> Default for FileSearcherBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl<'a> FileSearcherBuilder<'a> {
     pub fn new() -> Self {
         Self {
-            root: "./",
+            root: Path::new("./"),
             target_extension: Vec::new(),
             ignore_re: Vec::new(),
+            include_re: Vec::new(),
             ignore_filenames: Vec::new(),
             ignore_extension: Vec::new(),
+            case_insensitive_extensions: false,
+            follow_symlinks: false,
+            watch_dirs: false,
+            dir_include_re: Vec::new(),
+            limits: SearchLimits::default(),
         }
     }
     pub fn build(self) -> FileSearcher<'a> {
+        let case_insensitive = |extensions: Vec<Extension>| {
+            extensions
+                .into_iter()
+                .map(|extension| extension.case_insensitive(self.case_insensitive_extensions))
+                .collect::<Vec<_>>()
+        };
         FileSearcher {
             root: self.root,
-            target_extensions: Rc::new(self.target_extension),
-            ignore_re: Rc::new(self.ignore_re),
-            ignore_filenames: Rc::new(self.ignore_filenames),
-            ignore_extension: Rc::new(self.ignore_extension),
+            absolute_root: Arc::new(absolute(self.root)),
+            target_extensions: Arc::new(case_insensitive(self.target_extension)),
+            ignore_re: Arc::new(self.ignore_re),
+            include_re: Arc::new(self.include_re),
+            ignore_filenames: Arc::new(self.ignore_filenames),
+            ignore_extension: Arc::new(case_insensitive(self.ignore_extension)),
+            follow_symlinks: self.follow_symlinks,
+            watch_dirs: self.watch_dirs,
+            dir_include_re: Arc::new(self.dir_include_re),
+            limits: self.limits,
         }
     }
-    pub fn root(mut self, root: &'a str) -> Self {
-        self.root = root;
+    /// A `&str` or a `Path`, which may be a directory that is not UTF-8.
+    pub fn root<P: AsRef<Path> + ?Sized>(mut self, root: &'a P) -> Self {
+        self.set_root(root);
         self
     }
-    pub fn set_root(&mut self, root: &'a str) {
-        self.root = root;
+    pub fn set_root<P: AsRef<Path> + ?Sized>(&mut self, root: &'a P) {
+        self.root = root.as_ref();
     }
     pub fn target_extension(mut self, target_extension: &str) -> Self {
         self.target_extension
             .push(Extension::from(target_extension));
         self
     }
+    /// Matches target and ignore extensions regardless of case, e.g. `jpg` and `JPG`.
+    pub fn case_insensitive_extensions(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive_extensions = case_insensitive;
+        self
+    }
+    pub fn set_case_insensitive_extensions(&mut self, case_insensitive: bool) {
+        self.case_insensitive_extensions = case_insensitive;
+    }
+    /// Walks into symlinked directories too. A link into root or into a directory
+    /// already walked, e.g. to its parent, is not walked, and of several links to one
+    /// directory the first by path is.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+    pub fn set_follow_symlinks(&mut self, follow_symlinks: bool) {
+        self.follow_symlinks = follow_symlinks;
+    }
+    /// Reports the directories walked to `for_each_entry` too, so empty ones are watched.
+    pub fn watch_dirs(mut self, watch_dirs: bool) -> Self {
+        self.watch_dirs = watch_dirs;
+        self
+    }
+    pub fn set_watch_dirs(&mut self, watch_dirs: bool) {
+        self.watch_dirs = watch_dirs;
+    }
+    /// Reports only directories matching one of the regexes, which are written like ignore_re.
+    /// Directories are walked either way.
+    pub fn dir_include_re(mut self, re: &str) -> Self {
+        self.dir_include_re.push(path_pattern(re));
+        self
+    }
+    pub fn set_dir_include_re(&mut self, re: Vec<&str>) {
+        self.dir_include_re = re.into_iter().map(path_pattern).collect();
+    }
+    /// Directories deeper than `max_depth` below root are not walked, 0 watches only
+    /// the files directly in root.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.set_max_depth(max_depth);
+        self
+    }
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.limits.max_depth = Some(max_depth);
+    }
+    /// Files larger than `max_file_size` bytes are not watched.
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.set_max_file_size(max_file_size);
+        self
+    }
+    pub fn set_max_file_size(&mut self, max_file_size: u64) {
+        self.limits.max_file_size = Some(max_file_size);
+    }
+    /// Watches the first `max_files` files by path, with a warning about the rest.
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.set_max_files(max_files);
+        self
+    }
+    pub fn set_max_files(&mut self, max_files: usize) {
+        self.limits.max_files = Some(max_files);
+    }
+    /// Does not walk into mount points of other filesystems below root.
+    pub fn same_filesystem(mut self, same_filesystem: bool) -> Self {
+        self.set_same_filesystem(same_filesystem);
+        self
+    }
+    pub fn set_same_filesystem(&mut self, same_filesystem: bool) {
+        self.limits.same_filesystem = same_filesystem;
+    }
     pub fn ignore_filename(mut self, filename: &'a str) -> Self {
         self.ignore_filenames.push(filename);
         self
@@ -54,17 +157,29 @@ impl<'a> FileSearcherBuilder<'a> {
         self.ignore_extension.push(Extension::from(extension));
         self
     }
+    /// Ignores files and directories matching `re`, e.g. `^target$` by name, or
+    /// `relative:^src/generated/` by the path from root (see `PathPattern::new`).
     pub fn ignore_re(mut self, re: &'a str) -> Self {
-        let regex = Regex::new(re).unwrap();
-        self.ignore_re.push(regex);
+        self.ignore_re.push(path_pattern(re));
+        self
+    }
+    /// Watches only files matching one of the include regexes, in addition to
+    /// target_extensions. Directories are walked whether they match or not.
+    pub fn include_re(mut self, re: &'a str) -> Self {
+        self.include_re.push(path_pattern(re));
         self
     }
     pub fn set_ignore_files(&mut self, filenames: Vec<&'a str>) {
         self.ignore_filenames = filenames;
     }
     pub fn set_ignore_re(&mut self, re: Vec<&'a str>) {
-        let res = re.iter().map(|re| Regex::new(re).unwrap()).collect();
-        self.ignore_re = res;
+        self.ignore_re = re.into_iter().map(path_pattern).collect();
+    }
+    pub fn set_include_re(&mut self, re: Vec<&'a str>) {
+        self.include_re = re.into_iter().map(path_pattern).collect();
+    }
+    pub fn set_ignore_extensions(&mut self, extensions: Vec<&'a str>) {
+        self.ignore_extension = extensions.iter().map(|e| Extension::from(*e)).collect();
     }
     pub fn set_target_extensions(&mut self, target_extensions: Vec<&'a str>) {
         self.target_extension = target_extensions
@@ -72,102 +187,568 @@ impl<'a> FileSearcherBuilder<'a> {
             .map(|e| Extension::from(*e))
             .collect();
     }
+    /// Adds the target extensions and ignore regexes of `preset` to the ones already set.
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.set_preset(preset);
+        self
+    }
+    pub fn set_preset(&mut self, preset: Preset) {
+        for extension in preset.target_extensions() {
+            if !self.target_extension.contains(extension) {
+                self.target_extension.push(extension.clone());
+            }
+        }
+        for re in preset.ignore_path_words() {
+            if !self.ignore_re.iter().any(|pattern| pattern.as_str() == *re) {
+                self.ignore_re.push(path_pattern(re));
+            }
+        }
+    }
+}
+/// Guardrails against a root like `/` or a home directory.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct SearchLimits {
+    max_depth: Option<usize>,
+    max_file_size: Option<u64>,
+    max_files: Option<usize>,
+    same_filesystem: bool,
 }
+
+/// Files found by `FileSearcher::scan`, and what kept it from finding the rest.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FileScan {
+    pub files: Vec<PathBuf>,
+    pub warnings: Vec<String>,
+    /// paths passed over and why, only filled by `FileSearcher::list`
+    pub excluded: Vec<(PathBuf, Exclusion)>,
+}
+
+/// Why a path is not watched.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Exclusion {
+    IgnoreExtension(String),
+    IgnoreFilename(String),
+    /// the regex with its `relative:` or `absolute:` prefix
+    IgnoreRe(String),
+    NotTargetExtension,
+    NotIncluded,
+    TooLarge(u64),
+    TooDeep(usize),
+    OtherFilesystem,
+    /// a symlinked directory already walked by another path
+    AlreadyWalked,
+    NotUnderRoot,
+    /// a symlinked directory, which is walked only with follow_symlinks
+    SymlinkNotFollowed,
+    /// a directory above the path is not walked, for the reason of that directory
+    InExcludedDir(PathBuf, Box<Exclusion>),
+}
+impl Exclusion {
+    pub fn message(&self) -> String {
+        match self {
+            Self::IgnoreExtension(extension) => format!("ignore extension {}", extension),
+            Self::IgnoreFilename(filename) => format!("ignore filename {}", filename),
+            Self::IgnoreRe(re) => format!("ignore regex {}", re),
+            Self::NotTargetExtension => "not a target extension".to_string(),
+            Self::NotIncluded => "no include regex matches".to_string(),
+            Self::TooLarge(max_file_size) => {
+                format!("larger than max_file_size {} bytes", max_file_size)
+            }
+            Self::TooDeep(max_depth) => format!("deeper than max_depth {}", max_depth),
+            Self::OtherFilesystem => "on another filesystem than root".to_string(),
+            Self::AlreadyWalked => "already walked by another path".to_string(),
+            Self::NotUnderRoot => "not under root".to_string(),
+            Self::SymlinkNotFollowed => "a symlink, not followed".to_string(),
+            Self::InExcludedDir(dir, exclusion) => {
+                format!("in {}, {}", dir.display(), exclusion.message())
+            }
+        }
+    }
+}
+
+/// State shared by the tasks of one walk.
+struct Walk<'w> {
+    on_file: &'w (dyn Fn(PathBuf) + Sync),
+    on_dir: &'w (dyn Fn(PathBuf) + Sync),
+    with_excluded: bool,
+    excluded: Mutex<Vec<(PathBuf, Exclusion)>>,
+    /// with the directory each is about
+    warnings: Mutex<Vec<(PathBuf, String)>>,
+    /// symlinked directories found, walked once the walk that found them is done
+    links: Mutex<Vec<(PathBuf, usize)>>,
+    /// device of root when staying on its filesystem
+    device: Option<u64>,
+    cutoff: Option<Cutoff>,
+}
+
+/// The first max_files + 1 files by path, held until the walk is done, so the files
+/// kept do not depend on which thread is faster.
+struct Cutoff {
+    max_files: usize,
+    /// a max-heap, so the last of the files is on top
+    files: Mutex<BinaryHeap<PathBuf>>,
+    dirs: Mutex<Vec<PathBuf>>,
+    /// skips directories after the last of the files, as none of theirs can be kept
+    prune: bool,
+}
+
 #[derive(Debug)]
 pub struct FileSearcher<'a> {
-    root: &'a str,
-    target_extensions: Rc<Vec<Extension>>,
-    ignore_re: Rc<Vec<Regex>>,
-    ignore_filenames: Rc<Vec<&'a str>>,
-    ignore_extension: Rc<Vec<Extension>>,
+    root: &'a Path,
+    /// root from `/`, for absolute patterns
+    absolute_root: Arc<PathBuf>,
+    target_extensions: Arc<Vec<Extension>>,
+    ignore_re: Arc<Vec<PathPattern>>,
+    include_re: Arc<Vec<PathPattern>>,
+    ignore_filenames: Arc<Vec<&'a str>>,
+    ignore_extension: Arc<Vec<Extension>>,
+    follow_symlinks: bool,
+    watch_dirs: bool,
+    dir_include_re: Arc<Vec<PathPattern>>,
+    limits: SearchLimits,
 }
 impl<'a> FileSearcher<'a> {
-    pub fn spawn_child(&self, child_dir: &'a str) -> Self {
-        FileSearcher {
-            root: child_dir,
-            target_extensions: self.target_extensions.clone(),
-            ignore_re: self.ignore_re.clone(),
-            ignore_filenames: self.ignore_filenames.clone(),
-            ignore_extension: self.ignore_extension.clone(),
-        }
+    pub fn root(&self) -> &Path {
+        self.root
+    }
+    /// root from `/`
+    pub fn absolute_root(&self) -> &Path {
+        &self.absolute_root
     }
+    /// Sorted, as the walk finds files in no particular order.
     pub fn get_all_files(&self) -> Vec<PathBuf> {
-        let root_dir = fs::read_dir(self.root).expect(&format!("{} can not read_dir", self.root));
-        let mut all_files = Vec::new();
-        root_dir
-            .filter_map(|entry| entry.ok())
-            .filter_map(|entry| match entry.file_type() {
-                Ok(file_type) => Some((file_type, entry.path())),
-                Err(_) => None,
-            })
-            .for_each(|(file_type, path)| {
-                if self.is_ignore(&path) {
-                    return;
+        self.scan().files
+    }
+    /// Paths that are not UTF-8 are converted lossily, use `get_all_files` to keep them.
+    pub fn get_all_filenames(&self) -> Vec<String> {
+        self.get_all_files()
+            .into_iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect()
+    }
+    pub fn scan(&self) -> FileScan {
+        self.collect(false)
+    }
+    /// `scan` that records every path passed over and why as well, sorted by path.
+    /// The files of an excluded directory are not listed, only the directory.
+    pub fn list(&self) -> FileScan {
+        let mut scan = self.collect(true);
+        scan.excluded.sort_by(|(a, _), (b, _)| a.cmp(b));
+        scan
+    }
+    fn collect(&self, with_excluded: bool) -> FileScan {
+        let (sender, receiver) = mpsc::channel();
+        let (warnings, excluded) =
+            self.walk_root(&|path| sender.send(path).unwrap(), &|_| {}, with_excluded);
+        let mut files = receiver.try_iter().collect::<Vec<_>>();
+        files.sort();
+        FileScan {
+            files,
+            warnings,
+            excluded,
+        }
+    }
+    /// Why `path` is not watched, None when it is or would be once created.
+    /// max_files is left out, as the files over it depend on the whole walk.
+    pub fn explain(&self, path: &Path) -> Option<Exclusion> {
+        let absolute_path = normalize(path);
+        let Ok(relative) = absolute_path.strip_prefix(normalize(self.root)) else {
+            return Some(Exclusion::NotUnderRoot);
+        };
+        let device = if self.limits.same_filesystem {
+            device(self.root)
+        } else {
+            None
+        };
+        let mut current = self.root.to_path_buf();
+        let components = relative.components().collect::<Vec<_>>();
+        for (depth, component) in components.iter().enumerate() {
+            current.push(component);
+            let is_last = depth + 1 == components.len();
+            let exclusion = self.ignore_reason(&current).or_else(|| {
+                let is_dir = fs::metadata(&current).is_ok_and(|metadata| metadata.is_dir());
+                if is_last && !is_dir {
+                    return self
+                        .target_reason(&current)
+                        .or_else(|| self.size_reason(&current));
                 }
-                if file_type.is_dir() {
-                    let child = self.spawn_child(path.to_str().unwrap());
-                    all_files.append(&mut child.get_all_files());
-                    return;
+                if !self.follow_symlinks && current.is_symlink() {
+                    return Some(Exclusion::SymlinkNotFollowed);
                 }
-                if self.is_target(&path) {
-                    all_files.push(path);
-                    return;
+                self.limit_reason(&current, depth, device)
+            });
+            match exclusion {
+                Some(exclusion) if is_last => return Some(exclusion),
+                Some(exclusion) => {
+                    return Some(Exclusion::InExcludedDir(current, Box::new(exclusion)))
+                }
+                None => {}
+            }
+        }
+        None
+    }
+    /// Walks directories in parallel and calls `on_file` from the walking threads
+    /// with each target file as soon as it is found, or once the walk is done with
+    /// max_files. Returns the warnings of the walk, e.g. directories that can not be
+    /// read, which are skipped instead of stopping it.
+    pub fn for_each_file<F: Fn(PathBuf) + Sync>(&self, on_file: F) -> Vec<String> {
+        self.for_each_entry(on_file, |_| {})
+    }
+    /// `for_each_file` that calls `on_dir` with each directory walked below root
+    /// as well, when watch_dirs is set.
+    pub fn for_each_entry<F, D>(&self, on_file: F, on_dir: D) -> Vec<String>
+    where
+        F: Fn(PathBuf) + Sync,
+        D: Fn(PathBuf) + Sync,
+    {
+        self.walk_root(&on_file, &on_dir, false).0
+    }
+    /// Symlinked directories are walked after the walk that found them, in order of
+    /// path, so which of several links to a directory is walked does not depend on
+    /// which thread is faster.
+    fn walk_root(
+        &self,
+        on_file: &(dyn Fn(PathBuf) + Sync),
+        on_dir: &(dyn Fn(PathBuf) + Sync),
+        with_excluded: bool,
+    ) -> (Vec<String>, Vec<(PathBuf, Exclusion)>) {
+        let root = self.root;
+        let walk = Walk {
+            on_file,
+            on_dir,
+            with_excluded,
+            excluded: Mutex::new(Vec::new()),
+            warnings: Mutex::new(Vec::new()),
+            links: Mutex::new(Vec::new()),
+            device: if self.limits.same_filesystem {
+                device(root)
+            } else {
+                None
+            },
+            cutoff: self.limits.max_files.map(|max_files| Cutoff {
+                max_files,
+                files: Mutex::new(BinaryHeap::new()),
+                dirs: Mutex::new(Vec::new()),
+                // what is passed over and where links lead depends on every directory
+                prune: !with_excluded && !self.follow_symlinks,
+            }),
+        };
+        // directories the walks start from, not walked again when reached below
+        let mut starts = HashSet::new();
+        // a link into one of these is not followed, so a loop ends
+        let mut walked = Vec::new();
+        if self.follow_symlinks {
+            starts.extend(dir_id(root));
+            walked.extend(fs::canonicalize(root));
+        }
+        let mut dirs = vec![(root.to_path_buf(), 0)];
+        while !dirs.is_empty() {
+            rayon::scope(|scope| {
+                for (dir, depth) in dirs.drain(..) {
+                    let (walk, starts) = (&walk, &starts);
+                    scope.spawn(move |scope| self.walk(scope, dir, depth, walk, starts));
                 }
             });
-        all_files
+            let mut links = mem::take(&mut *walk.links.lock().unwrap());
+            links.sort();
+            for (link, depth) in links {
+                let Ok(target) = fs::canonicalize(&link) else {
+                    continue;
+                };
+                if walked.iter().any(|dir| target.starts_with(dir)) {
+                    walk.exclude(link, Exclusion::AlreadyWalked);
+                    continue;
+                }
+                walked.push(target);
+                starts.extend(dir_id(&link));
+                if self.is_watched_dir(&link) {
+                    walk.found_dir(link.clone());
+                }
+                dirs.push((link, depth));
+            }
+        }
+        let mut warnings = walk.warnings.into_inner().unwrap();
+        if let Some(cutoff) = walk.cutoff {
+            let mut files = cutoff.files.into_inner().unwrap().into_sorted_vec();
+            let mut dirs = cutoff.dirs.into_inner().unwrap();
+            if files.len() > cutoff.max_files {
+                // what is after the first file over max_files may be pruned or not
+                let over = files.pop().unwrap();
+                if cutoff.prune {
+                    dirs.retain(|dir| *dir < over);
+                    warnings.retain(|(dir, _)| *dir < over);
+                }
+                warnings.push((
+                    root.to_path_buf(),
+                    format!(
+                        "more than {} files under {}, so only {} are watched. check workspace or raise max_files",
+                        cutoff.max_files,
+                        root.display(),
+                        cutoff.max_files
+                    ),
+                ));
+            }
+            dirs.sort();
+            dirs.into_iter().for_each(on_dir);
+            files.into_par_iter().for_each(on_file);
+        }
+        let warnings = warnings.into_iter().map(|(_, warning)| warning).collect();
+        (warnings, walk.excluded.into_inner().unwrap())
     }
-    pub fn get_all_filenames(&self) -> Vec<String> {
-        let root_dir = fs::read_dir(self.root).expect(&format!("{} can not read_dir", self.root));
-        let mut all_files = Vec::new();
-        root_dir
-            .filter_map(|entry| entry.ok())
-            .filter_map(|entry| match entry.file_type() {
-                Ok(file_type) => Some((file_type, entry.path())),
-                Err(_) => None,
-            })
-            .for_each(|(file_type, path)| {
-                if self.is_ignore(&path) {
-                    return;
+    /// Hands each child directory to the thread pool, where idle threads steal it.
+    fn walk<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        dir: PathBuf,
+        depth: usize,
+        walk: &'s Walk,
+        starts: &'s HashSet<DirId>,
+    ) {
+        if walk
+            .cutoff
+            .as_ref()
+            .is_some_and(|cutoff| cutoff.is_pruned(&dir))
+        {
+            return;
+        }
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            // removed since its parent was read
+            Err(e) if e.kind() == ErrorKind::NotFound && depth > 0 => return,
+            Err(e) => {
+                let warning = format!("{} can not be read: {}", dir.display(), e);
+                walk.warn(dir, warning);
+                return;
+            }
+        };
+        let mut files = Vec::new();
+        let mut excluded = Vec::new();
+        let mut exclude = |path: PathBuf, exclusion: Exclusion| {
+            if walk.with_excluded {
+                excluded.push((path, exclusion));
+            }
+        };
+        for entry in read_dir {
+            let (file_type, path) = match entry
+                .and_then(|entry| entry.file_type().map(|file_type| (file_type, entry.path())))
+            {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let warning = format!("an entry of {} can not be read: {}", dir.display(), e);
+                    walk.warn(dir.clone(), warning);
+                    continue;
                 }
-                if file_type.is_dir() {
-                    let path = path.as_os_str().to_str().unwrap();
-                    let child = self.spawn_child(path);
-                    all_files.append(&mut child.get_all_filenames());
-                    return;
+            };
+            if let Some(exclusion) = self.ignore_reason(&path) {
+                exclude(path, exclusion);
+                continue;
+            }
+            let is_dir = if file_type.is_symlink() {
+                match fs::metadata(&path) {
+                    Ok(target) => target.is_dir(),
+                    // a broken link has nothing to watch
+                    Err(_) => continue,
                 }
-                if self.is_target(&path) {
-                    let path = path.as_os_str().to_str().unwrap();
-                    all_files.push(path.to_string());
-                    return;
+            } else {
+                file_type.is_dir()
+            };
+            if is_dir {
+                if let Some(exclusion) = self.limit_reason(&path, depth, walk.device) {
+                    exclude(path, exclusion);
+                } else if file_type.is_symlink() {
+                    if self.follow_symlinks {
+                        walk.links.lock().unwrap().push((path, depth + 1));
+                    } else {
+                        exclude(path, Exclusion::SymlinkNotFollowed);
+                    }
+                } else if !starts.is_empty() && dir_id(&path).is_some_and(|id| starts.contains(&id))
+                {
+                    exclude(path, Exclusion::AlreadyWalked);
+                } else {
+                    if self.is_watched_dir(&path) {
+                        walk.found_dir(path.clone());
+                    }
+                    scope.spawn(move |scope| self.walk(scope, path, depth + 1, walk, starts));
                 }
-            });
-        all_files
+                continue;
+            }
+            match self
+                .target_reason(&path)
+                .or_else(|| self.size_reason(&path))
+            {
+                Some(exclusion) => exclude(path, exclusion),
+                None if walk.cutoff.is_some() => files.push(path),
+                None => (walk.on_file)(path),
+            }
+        }
+        if let Some(cutoff) = &walk.cutoff {
+            cutoff.keep(files);
+        }
+        if !excluded.is_empty() {
+            walk.excluded.lock().unwrap().extend(excluded);
+        }
     }
-    fn is_ignore(&self, path: &PathBuf) -> bool {
-        let path_str = path.file_name().unwrap().to_str().unwrap();
-        self.is_ignore_extension(path)
-            || self.is_ignore_filename(path_str)
-            || self.is_ignore_re(path_str)
+    /// Whether max_depth or same_filesystem keeps `dir` from being walked.
+    fn limit_reason(
+        &self,
+        dir: &Path,
+        depth: usize,
+        root_device: Option<u64>,
+    ) -> Option<Exclusion> {
+        if let Some(max_depth) = self
+            .limits
+            .max_depth
+            .filter(|max_depth| depth >= *max_depth)
+        {
+            return Some(Exclusion::TooDeep(max_depth));
+        }
+        if root_device.is_some() && device(dir) != root_device {
+            return Some(Exclusion::OtherFilesystem);
+        }
+        None
+    }
+    fn is_watched_dir(&self, dir: &Path) -> bool {
+        self.watch_dirs
+            && (self.dir_include_re.is_empty() || self.is_match_any(&self.dir_include_re, dir))
+    }
+    fn size_reason(&self, path: &Path) -> Option<Exclusion> {
+        let max_file_size = self.limits.max_file_size?;
+        fs::metadata(path)
+            .is_ok_and(|metadata| metadata.len() > max_file_size)
+            .then_some(Exclusion::TooLarge(max_file_size))
+    }
+    fn ignore_reason(&self, path: &Path) -> Option<Exclusion> {
+        let filename = path.file_name()?;
+        if let Some(extension) = self
+            .ignore_extension
+            .iter()
+            .find(|extension| extension.is_match(path))
+        {
+            return Some(Exclusion::IgnoreExtension(extension.as_str().to_string()));
+        }
+        if let Some(ignore_filename) = self
+            .ignore_filenames
+            .iter()
+            .find(|ignore_filename| OsStr::new(ignore_filename) == filename)
+        {
+            return Some(Exclusion::IgnoreFilename(ignore_filename.to_string()));
+        }
+        self.ignore_re
+            .iter()
+            .find(|pattern| pattern.is_match(path, self.root, &self.absolute_root))
+            .map(|pattern| Exclusion::IgnoreRe(pattern.to_string()))
     }
-    fn is_target(&self, path: &PathBuf) -> bool {
-        self.target_extensions.len() == 0
+    fn target_reason(&self, path: &Path) -> Option<Exclusion> {
+        let is_target_extension = self.target_extensions.is_empty()
             || self
                 .target_extensions
                 .iter()
-                .any(|extension| extension.is_match(path))
+                .any(|extension| extension.is_match(path));
+        if !is_target_extension {
+            return Some(Exclusion::NotTargetExtension);
+        }
+        if !self.include_re.is_empty() && !self.is_match_any(&self.include_re, path) {
+            return Some(Exclusion::NotIncluded);
+        }
+        None
     }
-    fn is_ignore_extension(&self, path: &PathBuf) -> bool {
-        self.ignore_extension
+    fn is_match_any(&self, patterns: &[PathPattern], path: &Path) -> bool {
+        patterns
             .iter()
-            .any(|extension| extension.is_match(path))
+            .any(|pattern| pattern.is_match(path, self.root, &self.absolute_root))
     }
-    fn is_ignore_filename(&self, path: &str) -> bool {
-        self.ignore_filenames.contains(&path)
+}
+
+/// Panics like an invalid regex always did, as the config has to be fixed first.
+fn path_pattern(re: &str) -> PathPattern {
+    PathPattern::new(re).unwrap_or_else(|e| panic!("{}", e))
+}
+fn absolute(root: &Path) -> PathBuf {
+    std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf())
+}
+/// `absolute` with `.` and `..` folded away, without looking at the filesystem,
+/// so a `..` after a symlink is taken lexically.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in absolute(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
     }
-    fn is_ignore_re(&self, path: &str) -> bool {
-        self.ignore_re.iter().any(|re| re.is_match(path))
+    normalized
+}
+
+impl Walk<'_> {
+    fn warn(&self, dir: PathBuf, warning: String) {
+        self.warnings.lock().unwrap().push((dir, warning));
+    }
+    fn exclude(&self, path: PathBuf, exclusion: Exclusion) {
+        if self.with_excluded {
+            self.excluded.lock().unwrap().push((path, exclusion));
+        }
+    }
+    fn found_dir(&self, dir: PathBuf) {
+        match &self.cutoff {
+            Some(cutoff) => cutoff.dirs.lock().unwrap().push(dir),
+            None => (self.on_dir)(dir),
+        }
     }
 }
+impl Cutoff {
+    fn keep(&self, found: Vec<PathBuf>) {
+        if found.is_empty() {
+            return;
+        }
+        let mut files = self.files.lock().unwrap();
+        for file in found {
+            if files.len() <= self.max_files {
+                files.push(file);
+            } else if files.peek().is_some_and(|last| file < *last) {
+                files.pop();
+                files.push(file);
+            }
+        }
+    }
+    /// Whether every file in `dir` sorts after the first max_files + 1 found so far.
+    fn is_pruned(&self, dir: &Path) -> bool {
+        if !self.prune {
+            return false;
+        }
+        let files = self.files.lock().unwrap();
+        files.len() > self.max_files && files.peek().is_some_and(|last| dir > last.as_path())
+    }
+}
+
+/// The same directory, whatever path it is reached by.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(unix)]
+fn dir_id(path: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path)
+        .ok()
+        .map(|metadata| (metadata.dev(), metadata.ino()))
+}
+#[cfg(unix)]
+fn device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+#[cfg(not(unix))]
+type DirId = PathBuf;
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> Option<DirId> {
+    path.canonicalize().ok()
+}
+/// Mount points are not known here, so every directory is on the same filesystem.
+#[cfg(not(unix))]
+fn device(_: &Path) -> Option<u64> {
+    None
+}
 
 #[cfg(test)]
 mod test_filesearcher {
@@ -217,6 +798,318 @@ mod test_filesearcher {
         }
     }
     #[test]
+    fn test_get_all_filenames_case_language_preset() {
+        let filesearcher = FileSearcherBuilder::new()
+            .root("./tests")
+            .target_extension("txt")
+            .preset(Preset::Docs)
+            .build();
+        let all_flies = filesearcher.get_all_filenames();
+        assert!(all_flies.contains(&"./tests/test2/test2.txt".to_string()));
+        assert!(!all_flies.contains(&"./tests/test.rs".to_string()));
+        assert_eq!(
+            all_flies
+                .iter()
+                .filter(|file| file.ends_with("test2.txt"))
+                .count(),
+            1
+        );
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_get_all_filenames_case_follow_symlinks() {
+        use std::os::unix::fs::symlink;
+        let dir =
+            std::env::temp_dir().join(format!("monirs-test-filesearcher-{}", std::process::id()));
+        fs::create_dir_all(dir.join("vendor")).unwrap();
+        fs::create_dir_all(dir.join("root")).unwrap();
+        fs::write(dir.join("vendor/lib.rs"), "").unwrap();
+        symlink("../vendor", dir.join("root/vendor")).unwrap();
+        symlink(".", dir.join("root/loop")).unwrap();
+        let root = dir.join("root");
+        let root = root.to_str().unwrap();
+        let searcher = FileSearcherBuilder::new().root(root).target_extension("rs");
+        assert!(searcher.clone().build().get_all_filenames().is_empty());
+        assert_eq!(
+            searcher.follow_symlinks(true).build().get_all_filenames(),
+            vec![format!("{}/vendor/lib.rs", root)]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_get_all_files_case_symlink_from_sibling_branch() {
+        use std::os::unix::fs::symlink;
+        let dir = std::env::temp_dir().join(format!("monirs-test-sibling-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a/deep/real")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::write(dir.join("a/deep/real/lib.rs"), "").unwrap();
+        symlink("../a/deep/real", dir.join("b/link")).unwrap();
+        let searcher = FileSearcherBuilder::new().root(dir.to_str().unwrap());
+        assert_eq!(
+            searcher.clone().build().get_all_files(),
+            vec![dir.join("a/deep/real/lib.rs")]
+        );
+        assert!(searcher
+            .clone()
+            .build()
+            .list()
+            .excluded
+            .contains(&(dir.join("b/link"), Exclusion::SymlinkNotFollowed)));
+        // the real directory is walked, whichever branch a thread reaches first
+        let searcher = searcher.follow_symlinks(true).build();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        for _ in 0..20 {
+            let scan = pool.install(|| searcher.list());
+            assert_eq!(scan.files, vec![dir.join("a/deep/real/lib.rs")]);
+            assert!(scan
+                .excluded
+                .contains(&(dir.join("b/link"), Exclusion::AlreadyWalked)));
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_case_follow_symlinks_on_many_threads() {
+        use std::os::unix::fs::symlink;
+        let dir = std::env::temp_dir().join(format!("monirs-test-threads-{}", std::process::id()));
+        let root = dir.join("root");
+        fs::create_dir_all(dir.join("outside")).unwrap();
+        fs::create_dir_all(root.join("x/real")).unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            fs::write(dir.join("outside").join(name), "").unwrap();
+        }
+        fs::write(root.join("x/real/d.rs"), "").unwrap();
+        for link in ["w", "y", "z"] {
+            symlink("../outside", root.join(link)).unwrap();
+        }
+        symlink("x/real", root.join("v")).unwrap();
+        let searcher = FileSearcherBuilder::new()
+            .root(&root)
+            .follow_symlinks(true)
+            .max_files(2)
+            .build();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        let first = pool.install(|| searcher.list());
+        assert_eq!(first.files, vec![root.join("w/a.rs"), root.join("w/b.rs")]);
+        assert_eq!(
+            first
+                .excluded
+                .iter()
+                .filter(|(_, exclusion)| *exclusion == Exclusion::AlreadyWalked)
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>(),
+            vec![root.join("v"), root.join("y"), root.join("z")]
+        );
+        for _ in 0..20 {
+            assert_eq!(pool.install(|| searcher.list()), first);
+            assert_eq!(pool.install(|| searcher.scan().files), first.files);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn test_scan_case_max_files_on_many_threads() {
+        let dir = std::env::temp_dir().join(format!("monirs-test-cutoff-{}", std::process::id()));
+        for i in 0..30 {
+            let sub = dir.join(format!("{:02}/sub", i));
+            fs::create_dir_all(&sub).unwrap();
+            fs::write(dir.join(format!("{:02}/a.rs", i)), "").unwrap();
+            fs::write(sub.join("b.rs"), "").unwrap();
+        }
+        let searcher = FileSearcherBuilder::new().root(&dir).watch_dirs(true);
+        let all = searcher.clone().build().get_all_files();
+        let searcher = searcher.max_files(7).build();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        for _ in 0..20 {
+            let (sender, receiver) = mpsc::channel();
+            let scan = pool.install(|| {
+                let warnings = searcher.for_each_entry(|_| {}, |dir| sender.send(dir).unwrap());
+                (searcher.scan(), warnings)
+            });
+            assert_eq!(scan.0.files, all[..7]);
+            assert_eq!(scan.1.len(), 1);
+            let mut dirs = receiver.try_iter().collect::<Vec<_>>();
+            dirs.sort();
+            // the directories before the first file over max_files, 00 to 03/sub
+            assert_eq!(dirs.len(), 8);
+            assert_eq!(dirs.last(), Some(&dir.join("03/sub")));
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
+    fn test_scan_case_limits() {
+        let sorted = |searcher: FileSearcherBuilder| {
+            let mut files = searcher.build().get_all_filenames();
+            files.sort();
+            files
+        };
+        let searcher = FileSearcherBuilder::new().root("./tests");
+        assert_eq!(
+            sorted(searcher.clone().max_depth(0)),
+            vec!["./tests/test.rs"]
+        );
+        assert_eq!(
+            sorted(searcher.clone().max_depth(1)),
+            vec!["./tests/test.rs", "./tests/test2/test2.txt"]
+        );
+        assert_eq!(
+            sorted(searcher.clone().max_file_size(0)),
+            vec!["./tests/test.rs", "./tests/test2/test2.txt"]
+        );
+        let scan = searcher.clone().max_files(1).build().scan();
+        assert_eq!(scan.files, vec![PathBuf::from("./tests/test.rs")]);
+        assert_eq!(scan.warnings.len(), 1);
+        let scan = searcher.same_filesystem(true).max_files(3).build().scan();
+        assert_eq!(scan.files.len(), 3);
+        assert!(scan.warnings.is_empty());
+    }
+    #[test]
+    fn test_get_all_filenames_case_path_patterns() {
+        let searcher = FileSearcherBuilder::new().root("./tests");
+        assert_eq!(
+            searcher
+                .clone()
+                .ignore_re("relative:^test1/")
+                .ignore_re("relative:^test\\.rs$")
+                .build()
+                .get_all_filenames(),
+            vec!["./tests/test2/test2.txt"]
+        );
+        assert_eq!(
+            searcher
+                .clone()
+                .ignore_re("absolute:/tests/test2$")
+                .include_re("relative:^test1/.*/test\\.txt$")
+                .build()
+                .get_all_filenames(),
+            vec!["./tests/test1/test1-1/test1-1-1/test.txt"]
+        );
+        assert!(searcher
+            .include_re("^test2")
+            .target_extension("rs")
+            .build()
+            .get_all_filenames()
+            .is_empty());
+    }
+    #[test]
+    fn test_for_each_entry_case_watch_dirs() {
+        use std::sync::Mutex;
+        let dirs = Mutex::new(Vec::new());
+        let searcher = FileSearcherBuilder::new().root("./tests");
+        searcher
+            .clone()
+            .build()
+            .for_each_entry(|_| {}, |dir| dirs.lock().unwrap().push(dir));
+        assert!(dirs.lock().unwrap().is_empty());
+        searcher
+            .watch_dirs(true)
+            .dir_include_re("^test1")
+            .build()
+            .for_each_entry(|_| {}, |dir| dirs.lock().unwrap().push(dir));
+        let mut dirs = dirs.into_inner().unwrap();
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("./tests/test1"),
+                PathBuf::from("./tests/test1/test1-1"),
+                PathBuf::from("./tests/test1/test1-1/test1-1-1"),
+            ]
+        );
+    }
+    #[test]
+    fn test_list() {
+        let list = FileSearcherBuilder::new()
+            .root("./tests")
+            .target_extension("txt")
+            .ignore_re("^test1-1$")
+            .build()
+            .list();
+        assert_eq!(list.files, vec![PathBuf::from("./tests/test2/test2.txt")]);
+        assert_eq!(
+            list.excluded,
+            vec![
+                (
+                    PathBuf::from("./tests/test.rs"),
+                    Exclusion::NotTargetExtension
+                ),
+                (
+                    PathBuf::from("./tests/test1/test1-1"),
+                    Exclusion::IgnoreRe("^test1-1$".to_string())
+                ),
+            ]
+        );
+    }
+    #[test]
+    fn test_explain() {
+        let searcher = FileSearcherBuilder::new()
+            .root("./tests")
+            .target_extension("txt")
+            .ignore_filename("test2.txt")
+            .max_depth(2)
+            .build();
+        assert_eq!(
+            searcher.explain(Path::new("./tests/test1/test1-1/test1-1-1/test.txt")),
+            Some(Exclusion::InExcludedDir(
+                PathBuf::from("./tests/test1/test1-1/test1-1-1"),
+                Box::new(Exclusion::TooDeep(2))
+            ))
+        );
+        assert_eq!(
+            searcher.explain(Path::new("tests/test2/test2.txt")),
+            Some(Exclusion::IgnoreFilename("test2.txt".to_string()))
+        );
+        assert_eq!(
+            searcher.explain(Path::new("./tests/test.rs")),
+            Some(Exclusion::NotTargetExtension)
+        );
+        assert_eq!(searcher.explain(Path::new("./tests/new.txt")), None);
+        assert_eq!(
+            searcher.explain(Path::new("./src/lib.rs")),
+            Some(Exclusion::NotUnderRoot)
+        );
+    }
+    #[test]
+    fn test_scan_case_unreadable_root() {
+        let scan = FileSearcherBuilder::new()
+            .root("./tests/not-found")
+            .build()
+            .scan();
+        assert!(scan.files.is_empty());
+        assert_eq!(scan.warnings.len(), 1);
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_case_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        let dir = std::env::temp_dir().join(format!(
+            "monirs-test-filesearcher-utf8-{}",
+            std::process::id()
+        ));
+        let root = dir.join(OsStr::from_bytes(b"r\xe9pertoire"));
+        fs::create_dir_all(&root).unwrap();
+        let not_utf8 = root.join(OsStr::from_bytes(b"caf\xe9.rs"));
+        fs::write(&not_utf8, "").unwrap();
+        let scan = FileSearcherBuilder::new()
+            .root(&root)
+            .target_extension("rs")
+            .ignore_filename("other.rs")
+            .build()
+            .scan();
+        assert_eq!(scan.files, vec![not_utf8]);
+        assert!(scan.warnings.is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+    #[test]
     fn test_get_all_filenames_by_use_preset_tests_dir_case_ignore_txt() {
         let filesearcher = FileSearcherBuilder::new()
             .root("./tests")
@@ -295,6 +1188,22 @@ mod test_filesearcher {
         }
     }
     #[test]
+    fn test_get_all_filenames_case_insensitive_extensions() {
+        let filesearcher = FileSearcherBuilder::new()
+            .root("./tests")
+            .target_extension("TXT")
+            .build();
+        assert!(filesearcher.get_all_filenames().is_empty());
+        let filesearcher = FileSearcherBuilder::new()
+            .root("./tests")
+            .target_extension("TXT")
+            .case_insensitive_extensions(true)
+            .build();
+        assert!(filesearcher
+            .get_all_filenames()
+            .contains(&"./tests/test2/test2.txt".to_string()));
+    }
+    #[test]
     fn test_get_all_filenames_by_use_preset_tests_dir() {
         let filesearcher = FileSearcherBuilder::new().root("./tests").build();
         let all_flies = filesearcher.get_all_filenames();
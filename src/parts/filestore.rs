@@ -1,33 +1,413 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
 
 type FileSize = u128;
+
 #[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FileEvent {
+    Created(PathBuf),
+    Modified(PathBuf),
+    Removed(PathBuf),
+    DirCreated(PathBuf),
+    DirRemoved(PathBuf),
+}
+impl FileEvent {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Created(path)
+            | Self::Modified(path)
+            | Self::Removed(path)
+            | Self::DirCreated(path)
+            | Self::DirRemoved(path) => path,
+        }
+    }
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Created(_) => "created",
+            Self::Modified(_) => "modified",
+            Self::Removed(_) => "removed",
+            Self::DirCreated(_) => "dir_created",
+            Self::DirRemoved(_) => "dir_removed",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct FileStore {
-    store: HashMap<String, FileSize>,
+    store: HashMap<PathBuf, FileSize>,
+    /// targets of symlinks, so pointing a link to another file is a modification
+    links: HashMap<PathBuf, PathBuf>,
+    /// directories, when they are watched
+    dirs: HashSet<PathBuf>,
 }
 impl FileStore {
     pub fn new() -> Self {
         Self {
             store: HashMap::new(),
+            links: HashMap::new(),
+            dirs: HashSet::new(),
+        }
+    }
+    /// Loads a snapshot written by `save`.
+    pub fn from_file<P: AsRef<Path>>(filepath: P) -> Result<Self, String> {
+        let filepath = filepath.as_ref();
+        let file = File::open(filepath).map_err(|e| format!("{:?} {}", filepath, e))?;
+        serde_json::from_reader(BufReader::new(file))
+            .map(Snapshot::into_filestore)
+            .map_err(|e| format!("{:?} {}", filepath, e))
+    }
+    /// Writes the snapshot to a temporary file first, so an interrupted save keeps the old one.
+    pub fn save<P: AsRef<Path>>(&self, filepath: P) -> Result<(), String> {
+        let filepath = filepath.as_ref();
+        if let Some(parent) = filepath.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("{:?} {}", parent, e))?;
+        }
+        let tmp_filepath = filepath.with_extension("tmp");
+        let file = File::create(&tmp_filepath).map_err(|e| format!("{:?} {}", tmp_filepath, e))?;
+        serde_json::to_writer(BufWriter::new(file), &Snapshot::new(self))
+            .map_err(|e| format!("{:?} {}", tmp_filepath, e))?;
+        fs::rename(&tmp_filepath, filepath).map_err(|e| format!("{:?} {}", filepath, e))
+    }
+    /// The store with its files, links and dirs renamed by `f`. Link targets are kept as they are.
+    pub fn map_paths<F: Fn(&Path) -> PathBuf>(&self, f: F) -> Self {
+        Self {
+            store: self
+                .store
+                .iter()
+                .map(|(path, size)| (f(path), *size))
+                .collect(),
+            links: self
+                .links
+                .iter()
+                .map(|(path, target)| (f(path), target.clone()))
+                .collect(),
+            dirs: self.dirs.iter().map(|dir| f(dir)).collect(),
         }
     }
-    pub fn is_new(&self, path: &str) -> bool {
+    pub fn is_new(&self, path: &Path) -> bool {
         !self.store.contains_key(path)
     }
-    pub fn is_modify(&self, path: &str, filesize: FileSize) -> bool {
+    pub fn is_modify(&self, path: &Path, filesize: FileSize) -> bool {
         if let Some(old_filesize) = self.store.get(path) {
             return old_filesize != &filesize;
         }
         false
     }
-    pub fn update(&mut self, path: String, filesize: FileSize) {
+    pub fn update(&mut self, path: PathBuf, filesize: FileSize) {
         if self.is_modify(&path, filesize) {
             self.store.insert(path, filesize);
         }
     }
-    pub fn insert(&mut self, path: String, filesize: FileSize) {
+    pub fn insert(&mut self, path: PathBuf, filesize: FileSize) {
         if self.is_new(&path) {
             self.store.insert(path, filesize);
         }
     }
+    /// Remembers the target of the symlink at `path`.
+    pub fn insert_link(&mut self, path: PathBuf, target: PathBuf) {
+        self.links.insert(path, target);
+    }
+    pub fn insert_dir(&mut self, dir: PathBuf) {
+        self.dirs.insert(dir);
+    }
+    pub fn is_relinked(&self, path: &Path, target: Option<&PathBuf>) -> bool {
+        !self.is_new(path) && self.links.get(path) != target
+    }
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.store.keys()
+    }
+    pub fn remove(&mut self, path: &Path) {
+        self.store.remove(path);
+        self.links.remove(path);
+        self.dirs.remove(path);
+    }
+    /// Events that turn this store into `current`, sorted by path.
+    pub fn diff(&self, current: &FileStore) -> Vec<FileEvent> {
+        let mut events = current
+            .store
+            .iter()
+            .filter_map(|(path, size)| {
+                if self.is_new(path) {
+                    return Some(FileEvent::Created(path.clone()));
+                }
+                if self.is_modify(path, *size) || self.is_relinked(path, current.links.get(path)) {
+                    return Some(FileEvent::Modified(path.clone()));
+                }
+                None
+            })
+            .chain(
+                self.store
+                    .keys()
+                    .filter(|path| current.is_new(path))
+                    .map(|path| FileEvent::Removed(path.clone())),
+            )
+            .chain(
+                current
+                    .dirs
+                    .difference(&self.dirs)
+                    .map(|dir| FileEvent::DirCreated(dir.clone())),
+            )
+            .chain(
+                self.dirs
+                    .difference(&current.dirs)
+                    .map(|dir| FileEvent::DirRemoved(dir.clone())),
+            )
+            .collect::<Vec<_>>();
+        events.sort_by(|a, b| a.path().cmp(b.path()));
+        events
+    }
+}
+
+/// A FileStore as written to json. Paths are lists of pairs, since json keys
+/// can only be strings and a path may not be UTF-8.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    files: Vec<(SnapshotPath, FileSize)>,
+    #[serde(default)]
+    links: Vec<(SnapshotPath, SnapshotPath)>,
+    #[serde(default)]
+    dirs: Vec<SnapshotPath>,
+}
+impl Snapshot {
+    fn new(filestore: &FileStore) -> Self {
+        Self {
+            files: filestore
+                .store
+                .iter()
+                .map(|(path, size)| (SnapshotPath::new(path), *size))
+                .collect(),
+            links: filestore
+                .links
+                .iter()
+                .map(|(path, target)| (SnapshotPath::new(path), SnapshotPath::new(target)))
+                .collect(),
+            dirs: filestore
+                .dirs
+                .iter()
+                .map(|dir| SnapshotPath::new(dir))
+                .collect(),
+        }
+    }
+    fn into_filestore(self) -> FileStore {
+        FileStore {
+            store: self
+                .files
+                .into_iter()
+                .map(|(path, size)| (path.into_path(), size))
+                .collect(),
+            links: self
+                .links
+                .into_iter()
+                .map(|(path, target)| (path.into_path(), target.into_path()))
+                .collect(),
+            dirs: self.dirs.into_iter().map(SnapshotPath::into_path).collect(),
+        }
+    }
+}
+
+/// A path as a string, or as its raw bytes when it is not UTF-8.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SnapshotPath {
+    Utf8(String),
+    Bytes(Vec<u8>),
+}
+impl SnapshotPath {
+    fn new(path: &Path) -> Self {
+        match path.to_str() {
+            Some(path) => Self::Utf8(path.to_string()),
+            None => Self::Bytes(path_to_bytes(path)),
+        }
+    }
+    fn into_path(self) -> PathBuf {
+        match self {
+            Self::Utf8(path) => PathBuf::from(path),
+            Self::Bytes(bytes) => path_from_bytes(bytes),
+        }
+    }
+}
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+    PathBuf::from(OsString::from_vec(bytes))
+}
+// other platforms only see non-UTF-8 paths from ill-formed UTF-16, which are kept lossily
+#[cfg(not(unix))]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The roots of a moni as given and as found on disk, so snapshot keys are the same
+/// however the roots are spelled and wherever moni is started from.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotRoots {
+    roots: Vec<(PathBuf, PathBuf)>,
+}
+impl SnapshotRoots {
+    pub fn new<'r, I: IntoIterator<Item = &'r Path>>(roots: I) -> Self {
+        Self {
+            roots: roots
+                .into_iter()
+                .map(|root| (root.to_path_buf(), on_disk(root)))
+                .collect(),
+        }
+    }
+    /// `path` as a key of the snapshot.
+    pub fn to_snapshot(&self, path: &Path) -> PathBuf {
+        rebase(path, self.roots.iter().map(|(given, disk)| (given, disk)))
+    }
+    /// A key of the snapshot as the path moni scans.
+    pub fn from_snapshot(&self, path: &Path) -> PathBuf {
+        rebase(path, self.roots.iter().map(|(given, disk)| (disk, given)))
+    }
+}
+/// `path` moved from the deepest `from` it is under to its `to`, or as it is.
+fn rebase<'r, I: Iterator<Item = (&'r PathBuf, &'r PathBuf)>>(path: &Path, roots: I) -> PathBuf {
+    roots
+        .filter_map(|(from, to)| {
+            let rest = path.strip_prefix(from).ok()?;
+            Some((from.components().count(), to, rest))
+        })
+        .max_by_key(|(depth, _, _)| *depth)
+        .map(|(_, to, rest)| match rest.as_os_str().is_empty() {
+            true => to.clone(),
+            false => to.join(rest),
+        })
+        .unwrap_or_else(|| path.to_path_buf())
+}
+/// `path` with symlinks and `..` resolved as far as it exists.
+fn on_disk(path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(path) {
+        return path;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => on_disk(parent).join(name),
+        (Some(_), Some(name)) => on_disk(Path::new(".")).join(name),
+        _ => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod test_filestore {
+    use super::*;
+    #[test]
+    fn test_diff() {
+        let mut old = FileStore::new();
+        old.insert(PathBuf::from("same.rs"), 1);
+        old.insert(PathBuf::from("modified.rs"), 1);
+        old.insert(PathBuf::from("removed.rs"), 1);
+        let mut current = FileStore::new();
+        current.insert(PathBuf::from("same.rs"), 1);
+        current.insert(PathBuf::from("modified.rs"), 2);
+        current.insert(PathBuf::from("created.rs"), 1);
+        assert_eq!(
+            old.diff(&current),
+            vec![
+                FileEvent::Created(PathBuf::from("created.rs")),
+                FileEvent::Modified(PathBuf::from("modified.rs")),
+                FileEvent::Removed(PathBuf::from("removed.rs")),
+            ]
+        );
+    }
+    #[test]
+    fn test_diff_case_relinked() {
+        let mut old = FileStore::new();
+        old.insert(PathBuf::from("link.rs"), 1);
+        old.insert_link(PathBuf::from("link.rs"), PathBuf::from("a.rs"));
+        let mut current = old.clone();
+        assert!(old.diff(&current).is_empty());
+        current.insert_link(PathBuf::from("link.rs"), PathBuf::from("b.rs"));
+        assert_eq!(
+            old.diff(&current),
+            vec![FileEvent::Modified(PathBuf::from("link.rs"))]
+        );
+    }
+    #[test]
+    fn test_diff_case_dirs() {
+        let mut old = FileStore::new();
+        old.insert_dir(PathBuf::from("migrations/001"));
+        old.insert_dir(PathBuf::from("migrations/002"));
+        let mut current = FileStore::new();
+        current.insert_dir(PathBuf::from("migrations/002"));
+        current.insert_dir(PathBuf::from("migrations/003"));
+        assert_eq!(
+            old.diff(&current),
+            vec![
+                FileEvent::DirRemoved(PathBuf::from("migrations/001")),
+                FileEvent::DirCreated(PathBuf::from("migrations/003")),
+            ]
+        );
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_save_case_not_utf8() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let filepath = std::env::temp_dir().join(format!(
+            "monirs-test-filestore-utf8-{}/state.json",
+            std::process::id()
+        ));
+        let not_utf8 = PathBuf::from(OsStr::from_bytes(b"caf\xe9.rs"));
+        let mut store = FileStore::new();
+        store.insert(PathBuf::from("test.rs"), 10);
+        store.insert(not_utf8.clone(), 10);
+        store.insert_link(not_utf8.clone(), PathBuf::from(OsStr::from_bytes(b"\xff")));
+        store.insert_dir(PathBuf::from(OsStr::from_bytes(b"d\xe9")));
+        store.save(&filepath).unwrap();
+        assert_eq!(FileStore::from_file(&filepath).unwrap(), store);
+        fs::remove_dir_all(filepath.parent().unwrap()).unwrap();
+    }
+    #[test]
+    fn test_snapshot_roots() {
+        let dir = std::env::temp_dir().join(format!(
+            "monirs-test-filestore-roots-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(dir.join("root")).unwrap();
+        fs::create_dir_all(dir.join("x")).unwrap();
+        let disk = fs::canonicalize(dir.join("root")).unwrap();
+        let spelled = dir.join("x/../root");
+        let roots = SnapshotRoots::new([spelled.as_path(), Path::new("missing/file.rs")]);
+        assert_eq!(roots.to_snapshot(&spelled.join("a.md")), disk.join("a.md"));
+        assert_eq!(roots.to_snapshot(&spelled), disk);
+        assert_eq!(
+            roots.from_snapshot(&disk.join("a.md")),
+            spelled.join("a.md")
+        );
+        assert_eq!(
+            roots.to_snapshot(Path::new("missing/file.rs")),
+            std::env::current_dir().unwrap().join("missing/file.rs")
+        );
+        assert_eq!(
+            roots.to_snapshot(Path::new("other.rs")),
+            PathBuf::from("other.rs")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_save_and_from_file() {
+        let filepath = std::env::temp_dir().join(format!(
+            "monirs-test-filestore-{}/state.json",
+            std::process::id()
+        ));
+        let mut store = FileStore::new();
+        store.insert(PathBuf::from("test.rs"), 10);
+        store.save(&filepath).unwrap();
+        assert_eq!(FileStore::from_file(&filepath).unwrap(), store);
+        fs::remove_dir_all(filepath.parent().unwrap()).unwrap();
+    }
 }
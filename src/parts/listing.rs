@@ -0,0 +1,158 @@
+use std::{fmt::Write, path::Path};
+
+use super::filesearcher::{normalize, Exclusion, FileSearcher};
+
+/// What `moni ls` prints: the watched files of each root, the excluded paths with
+/// the rule that excluded them, and the watch files.
+pub fn ls(searchers: &[FileSearcher], watch_files: &[&Path]) -> String {
+    let mut out = String::new();
+    for searcher in searchers {
+        let list = searcher.list();
+        writeln!(out, "{}", searcher.root().display()).unwrap();
+        for file in &list.files {
+            writeln!(out, "  watched  {}", file.display()).unwrap();
+        }
+        for (path, exclusion) in &list.excluded {
+            writeln!(
+                out,
+                "  excluded {} ({})",
+                path.display(),
+                exclusion.message()
+            )
+            .unwrap();
+        }
+        for warning in &list.warnings {
+            writeln!(out, "  warning  {}", warning).unwrap();
+        }
+    }
+    if !watch_files.is_empty() {
+        writeln!(out, "watch_files").unwrap();
+        for path in watch_files {
+            if path.is_file() {
+                writeln!(out, "  watched  {}", path.display()).unwrap();
+            } else {
+                writeln!(out, "  missing  {} (watched once created)", path.display()).unwrap();
+            }
+        }
+    }
+    out
+}
+
+/// What `moni explain <path>` prints: whether each root above `path` watches it,
+/// and the rule that excluded it when not.
+pub fn explain(searchers: &[FileSearcher], watch_files: &[&Path], path: &Path) -> String {
+    let mut out = String::new();
+    for searcher in searchers {
+        match searcher.explain(path) {
+            None => writeln!(
+                out,
+                "{} is watched under {}",
+                path.display(),
+                searcher.root().display()
+            )
+            .unwrap(),
+            Some(Exclusion::NotUnderRoot) => {}
+            Some(exclusion) => writeln!(
+                out,
+                "{} is not watched under {}: {}",
+                path.display(),
+                searcher.root().display(),
+                exclusion.message()
+            )
+            .unwrap(),
+        }
+    }
+    let normalized_path = normalize(path);
+    if watch_files
+        .iter()
+        .any(|watch_file| normalize(watch_file) == normalized_path)
+    {
+        writeln!(out, "{} is watched as a watch file", path.display()).unwrap();
+    }
+    if out.is_empty() {
+        writeln!(
+            out,
+            "{} is not under any workspace or watch file",
+            path.display()
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod test_listing {
+    use super::*;
+    use crate::parts::filesearcher::FileSearcherBuilder;
+    fn searchers() -> Vec<FileSearcher<'static>> {
+        vec![FileSearcherBuilder::new()
+            .root("./tests")
+            .target_extension("txt")
+            .ignore_filename("test2.txt")
+            .max_depth(2)
+            .build()]
+    }
+    #[test]
+    fn test_ls() {
+        let out = ls(
+            &searchers(),
+            &[Path::new("./Cargo.toml"), Path::new("./later.env")],
+        );
+        assert!(out.starts_with("./tests\n"));
+        assert!(out.contains("  excluded ./tests/test2/test2.txt (ignore filename test2.txt)\n"));
+        assert!(out.contains("watch_files\n  watched  ./Cargo.toml\n"));
+        assert!(out.contains("  missing  ./later.env (watched once created)\n"));
+    }
+    #[test]
+    fn test_explain_case_included() {
+        assert_eq!(
+            explain(&searchers(), &[], Path::new("./tests/new.txt")),
+            "./tests/new.txt is watched under ./tests\n"
+        );
+    }
+    #[test]
+    fn test_explain_case_ignored() {
+        assert_eq!(
+            explain(&searchers(), &[], Path::new("./tests/test2/test2.txt")),
+            "./tests/test2/test2.txt is not watched under ./tests: ignore filename test2.txt\n"
+        );
+    }
+    #[test]
+    fn test_explain_case_over_limit() {
+        assert_eq!(
+            explain(
+                &searchers(),
+                &[],
+                Path::new("./tests/test1/test1-1/test1-1-1/test.txt")
+            ),
+            "./tests/test1/test1-1/test1-1-1/test.txt is not watched under ./tests: \
+             in ./tests/test1/test1-1/test1-1-1, deeper than max_depth 2\n"
+        );
+    }
+    #[test]
+    fn test_explain_case_outside_root() {
+        assert_eq!(
+            explain(&searchers(), &[], Path::new("./src/lib.rs")),
+            "./src/lib.rs is not under any workspace or watch file\n"
+        );
+        assert_eq!(
+            explain(
+                &searchers(),
+                &[Path::new("./Cargo.toml")],
+                Path::new("./src/../Cargo.toml")
+            ),
+            "./src/../Cargo.toml is watched as a watch file\n"
+        );
+    }
+    #[test]
+    fn test_explain_case_parent_dir() {
+        assert_eq!(
+            explain(&searchers(), &[], Path::new("./src/../tests/new.txt")),
+            "./src/../tests/new.txt is watched under ./tests\n"
+        );
+        assert_eq!(
+            explain(&searchers(), &[], Path::new("./tests/../src/new.txt")),
+            "./tests/../src/new.txt is not under any workspace or watch file\n"
+        );
+    }
+}
@@ -1,3 +1,7 @@
+use std::path::Path;
+
+use super::workspace::relative_path;
+
 pub struct MoniExecuteCommand<'a> {
     origin_command: &'a str,
 }
@@ -9,6 +13,26 @@ impl<'a> MoniExecuteCommand<'a> {
     pub fn to_execute_command(&self, filepath: &str) -> String {
         self.origin_command.replace(Self::MONI_MARK, filepath)
     }
+    /// Also replaces `{root}` with the workspace of `filepath` and `{relpath}` with
+    /// `filepath` from there.
+    /// Paths that are not UTF-8 are put in the command lossily.
+    pub fn to_workspace_command(&self, root: &Path, filepath: &Path) -> String {
+        self.to_execute_command(&filepath.to_string_lossy())
+            .replace("{root}", &root.to_string_lossy())
+            .replace(
+                "{relpath}",
+                &relative_path(root, filepath).to_string_lossy(),
+            )
+    }
+    /// `to_workspace_command` for a file outside every workspace, e.g. a watch file,
+    /// with its directory as `{root}`.
+    pub fn to_file_command(&self, filepath: &Path) -> String {
+        let root = match filepath.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        self.to_workspace_command(root, filepath)
+    }
 }
 #[cfg(test)]
 mod test_moni_execute_command {
@@ -31,4 +55,27 @@ mod test_moni_execute_command {
             format!("python test.py")
         )
     }
+    #[test]
+    fn test_workspace_command() {
+        let moni_exe = MoniExecuteCommand::new("cd {root} && protoc {relpath}");
+        assert_eq!(
+            moni_exe.to_workspace_command(
+                Path::new("../shared-proto"),
+                Path::new("../shared-proto/api/a.proto")
+            ),
+            "cd ../shared-proto && protoc api/a.proto"
+        )
+    }
+    #[test]
+    fn test_file_command() {
+        let moni_exe = MoniExecuteCommand::new("cd {root} && cat {relpath}");
+        assert_eq!(
+            moni_exe.to_file_command(Path::new("../config/.env")),
+            "cd ../config && cat .env"
+        );
+        assert_eq!(
+            moni_exe.to_file_command(Path::new("Cargo.toml")),
+            "cd . && cat Cargo.toml"
+        );
+    }
 }
@@ -0,0 +1,162 @@
+use std::{
+    process::{Command, Stdio},
+    thread,
+};
+
+use super::executor::{shell_command, ExecuteStatus, ExecutionResult};
+
+/// Told about every finished execution, e.g. to notice failures while moni runs
+/// in a background terminal. Canceled executions are not notified.
+pub trait Notifier {
+    fn notify(&self, result: &ExecutionResult) -> Result<(), String>;
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum NotifyOn {
+    Always,
+    Success,
+    /// errors and timeouts
+    #[default]
+    Failure,
+}
+impl NotifyOn {
+    pub fn new(on: &str) -> Result<Self, String> {
+        match on {
+            "always" => Ok(Self::Always),
+            "success" => Ok(Self::Success),
+            "failure" => Ok(Self::Failure),
+            _ => Err(format!(
+                "{} is not notify on. use always, success or failure",
+                on
+            )),
+        }
+    }
+    pub fn is_match(&self, result: &ExecutionResult) -> bool {
+        match *self {
+            Self::Always => true,
+            Self::Success => result.is_success(),
+            Self::Failure => !result.is_success(),
+        }
+    }
+}
+
+/// Shows a freedesktop notification through `notify-send`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DesktopNotifier {
+    on: NotifyOn,
+}
+impl DesktopNotifier {
+    pub fn new(on: NotifyOn) -> Self {
+        Self { on }
+    }
+}
+impl Notifier for DesktopNotifier {
+    fn notify(&self, result: &ExecutionResult) -> Result<(), String> {
+        if !self.on.is_match(result) {
+            return Ok(());
+        }
+        let urgency = match result.status() {
+            ExecuteStatus::Success => "normal",
+            _ => "critical",
+        };
+        let mut notify_send = Command::new("notify-send");
+        notify_send
+            .arg("--app-name=moni")
+            .arg(format!("--urgency={}", urgency))
+            .arg(format!("moni {}", result.status().as_str()))
+            .arg(result.command());
+        spawn_and_forget(notify_send).map_err(|e| format!("notify-send can not run {}", e))
+    }
+}
+
+/// Runs a shell command with MONI_STATUS, MONI_EXIT_CODE and MONI_COMMAND set,
+/// e.g. `curl -d "$MONI_COMMAND $MONI_STATUS" https://example.com/hook`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HookNotifier {
+    on: NotifyOn,
+    command: String,
+}
+impl HookNotifier {
+    pub fn new(on: NotifyOn, command: &str) -> Self {
+        Self {
+            on,
+            command: command.to_string(),
+        }
+    }
+}
+impl Notifier for HookNotifier {
+    fn notify(&self, result: &ExecutionResult) -> Result<(), String> {
+        if !self.on.is_match(result) {
+            return Ok(());
+        }
+        let mut hook = shell_command(&self.command);
+        hook.env("MONI_STATUS", result.status().as_str())
+            .env(
+                "MONI_EXIT_CODE",
+                result
+                    .exit_code()
+                    .map(|code| code.to_string())
+                    .unwrap_or_default(),
+            )
+            .env("MONI_COMMAND", result.command());
+        spawn_and_forget(hook).map_err(|e| format!("{} can not run {}", self.command, e))
+    }
+}
+
+/// Notifications must not hold up monitaring, so only a thread waits for them.
+fn spawn_and_forget(mut command: Command) -> std::io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_notifier {
+    use std::{fs, path::Path, thread, time::Duration};
+
+    use super::*;
+
+    fn result(status: &Result<String, String>) -> ExecutionResult {
+        ExecutionResult::from_fn_result("test.rs", status, Duration::ZERO)
+    }
+    #[test]
+    fn test_notify_on() {
+        let ok = result(&Ok("ok".to_string()));
+        let error = result(&Err("error".to_string()));
+        assert!(NotifyOn::Always.is_match(&ok));
+        assert!(NotifyOn::Success.is_match(&ok));
+        assert!(!NotifyOn::Success.is_match(&error));
+        assert!(NotifyOn::Failure.is_match(&error));
+        assert!(!NotifyOn::Failure.is_match(&ok));
+    }
+    #[test]
+    fn test_hook_notifier() {
+        let filepath =
+            std::env::temp_dir().join(format!("monirs-test-hook-{}.txt", std::process::id()));
+        let hook = HookNotifier::new(
+            NotifyOn::Failure,
+            &format!(
+                "echo \"$MONI_COMMAND $MONI_STATUS\" > {}",
+                filepath.display()
+            ),
+        );
+        hook.notify(&result(&Ok("ok".to_string()))).unwrap();
+        hook.notify(&result(&Err("error".to_string()))).unwrap();
+        let mut content = String::new();
+        for _ in 0..100 {
+            if Path::new(&filepath).exists() {
+                content = fs::read_to_string(&filepath).unwrap();
+                if !content.is_empty() {
+                    break;
+                }
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(content, "test.rs error\n");
+        fs::remove_file(&filepath).unwrap();
+    }
+}
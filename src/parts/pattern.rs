@@ -0,0 +1,142 @@
+use std::{
+    fmt::Display,
+    path::{Component, Path},
+};
+
+use regex::Regex;
+
+/// What part of a path a `PathPattern` is matched against.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum MatchTarget {
+    /// the file or directory name, e.g. `lib.rs`
+    #[default]
+    Basename,
+    /// the path from the workspace root joined by `/`, e.g. `src/generated/lib.rs`
+    Relative,
+    /// the path from `/`, e.g. `/home/moni/app/src/lib.rs`
+    Absolute,
+}
+impl MatchTarget {
+    pub fn new(target: &str) -> Result<Self, String> {
+        match target {
+            "basename" => Ok(Self::Basename),
+            "relative" => Ok(Self::Relative),
+            "absolute" => Ok(Self::Absolute),
+            _ => Err(format!(
+                "{} is not match target. use basename, relative or absolute",
+                target
+            )),
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Basename => "basename",
+            Self::Relative => "relative",
+            Self::Absolute => "absolute",
+        }
+    }
+}
+
+/// A regex with the part of the path it is matched against.
+#[derive(Debug, Clone)]
+pub struct PathPattern {
+    regex: Regex,
+    target: MatchTarget,
+}
+impl PathPattern {
+    /// `relative:src/generated/.*` or `absolute:^/tmp/` choose the target,
+    /// a pattern without one of them is matched against the basename.
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let (target, re) = match pattern.split_once(':') {
+            Some((target, re)) => match MatchTarget::new(target) {
+                Ok(target) => (target, re),
+                Err(_) => (MatchTarget::Basename, pattern),
+            },
+            None => (MatchTarget::Basename, pattern),
+        };
+        Self::with_target(re, target)
+    }
+    pub fn with_target(re: &str, target: MatchTarget) -> Result<Self, String> {
+        let regex = Regex::new(re).map_err(|e| format!("{} is not regex: {}", re, e))?;
+        Ok(Self { regex, target })
+    }
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+    pub fn target(&self) -> MatchTarget {
+        self.target
+    }
+    /// `root` is the workspace root `path` was found under, `absolute_root` the same
+    /// root from `/`.
+    pub fn is_match(&self, path: &Path, root: &Path, absolute_root: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        match self.target {
+            MatchTarget::Basename => path
+                .file_name()
+                .is_some_and(|name| self.regex.is_match(&name.to_string_lossy())),
+            MatchTarget::Relative => self.regex.is_match(&slash_joined(relative)),
+            MatchTarget::Absolute => self
+                .regex
+                .is_match(&slash_joined(&absolute_root.join(relative))),
+        }
+    }
+}
+
+/// The pattern as it is written in the config, with its prefix.
+impl Display for PathPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.target {
+            MatchTarget::Basename => write!(f, "{}", self.as_str()),
+            target => write!(f, "{}:{}", target.as_str(), self.as_str()),
+        }
+    }
+}
+
+/// `/` on every platform, so one pattern works on windows too.
+fn slash_joined(path: &Path) -> String {
+    let mut joined = String::new();
+    for component in path.components() {
+        match component {
+            Component::RootDir => joined.push('/'),
+            Component::Prefix(prefix) => joined.push_str(&prefix.as_os_str().to_string_lossy()),
+            component => {
+                if !joined.is_empty() && !joined.ends_with('/') {
+                    joined.push('/');
+                }
+                joined.push_str(&component.as_os_str().to_string_lossy());
+            }
+        }
+    }
+    joined
+}
+
+#[cfg(test)]
+mod test_pattern {
+    use super::*;
+    #[test]
+    fn test_new() {
+        let pattern = PathPattern::new("relative:src/generated/.*").unwrap();
+        assert_eq!(pattern.target(), MatchTarget::Relative);
+        assert_eq!(pattern.as_str(), "src/generated/.*");
+        let pattern = PathPattern::new("^[a-z]:x$").unwrap();
+        assert_eq!(pattern.target(), MatchTarget::Basename);
+        assert_eq!(pattern.as_str(), "^[a-z]:x$");
+        assert!(PathPattern::new("relative:(").is_err());
+    }
+    #[test]
+    fn test_is_match() {
+        let root = Path::new("./");
+        let absolute_root = Path::new("/home/moni/app");
+        let path = Path::new("./src/generated/lib.rs");
+        let is_match = |pattern: &str| {
+            PathPattern::new(pattern)
+                .unwrap()
+                .is_match(path, root, absolute_root)
+        };
+        assert!(is_match("^lib"));
+        assert!(!is_match("generated"));
+        assert!(is_match("relative:^src/generated/.*"));
+        assert!(!is_match("relative:^generated"));
+        assert!(is_match("absolute:^/home/moni/app/src/"));
+    }
+}
@@ -0,0 +1,134 @@
+use super::extensions::Extension;
+
+/// Usual target and ignore sets and a default command of a kind of project.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Preset {
+    Rust,
+    Node,
+    Python,
+    Java,
+    Web,
+    Docs,
+}
+impl Preset {
+    pub fn new(preset: &str) -> Result<Self, String> {
+        match preset {
+            "rust" => Ok(Self::Rust),
+            "node" => Ok(Self::Node),
+            "python" => Ok(Self::Python),
+            "java" => Ok(Self::Java),
+            "web" => Ok(Self::Web),
+            "docs" => Ok(Self::Docs),
+            _ => Err(format!(
+                "{} is not preset. use rust, node, python, java, web or docs",
+                preset
+            )),
+        }
+    }
+    pub fn target_extensions(&self) -> &'static [Extension] {
+        const RUST: &[Extension] = &[Extension::RS, Extension::TOML];
+        const NODE: &[Extension] = &[
+            Extension::JS,
+            Extension::MJS,
+            Extension::CJS,
+            Extension::TS,
+            Extension::JSX,
+            Extension::TSX,
+            Extension::JSON,
+        ];
+        const PYTHON: &[Extension] = &[Extension::PY, Extension::TOML, Extension::CFG];
+        const JAVA: &[Extension] = &[
+            Extension::JAVA,
+            Extension::KT,
+            Extension::XML,
+            Extension::GRADLE,
+            Extension::PROPERTIES,
+        ];
+        const WEB: &[Extension] = &[
+            Extension::HTML,
+            Extension::CSS,
+            Extension::SCSS,
+            Extension::JS,
+            Extension::TS,
+            Extension::JSX,
+            Extension::TSX,
+            Extension::VUE,
+            Extension::SVELTE,
+        ];
+        const DOCS: &[Extension] = &[
+            Extension::MD,
+            Extension::RST,
+            Extension::ADOC,
+            Extension::TXT,
+            Extension::YML,
+            Extension::YAML,
+        ];
+        match *self {
+            Self::Rust => RUST,
+            Self::Node => NODE,
+            Self::Python => PYTHON,
+            Self::Java => JAVA,
+            Self::Web => WEB,
+            Self::Docs => DOCS,
+        }
+    }
+    /// Regexes of directory and file names, anchored so `target` keeps `targets.rs`.
+    pub fn ignore_path_words(&self) -> &'static [&'static str] {
+        match *self {
+            Self::Rust => &[r"^\.git$", "^target$"],
+            Self::Node | Self::Web => &[
+                r"^\.git$",
+                "^node_modules$",
+                "^dist$",
+                "^build$",
+                "^coverage$",
+                r"^\.next$",
+            ],
+            Self::Python => &[
+                r"^\.git$",
+                "^__pycache__$",
+                r"^\.?venv$",
+                r"^\.mypy_cache$",
+                r"^\.pytest_cache$",
+                r"\.egg-info$",
+            ],
+            Self::Java => &[r"^\.git$", "^target$", "^build$", r"^\.gradle$"],
+            Self::Docs => &[r"^\.git$", "^_build$", "^site$"],
+        }
+    }
+    /// Used when no execute command is set.
+    pub fn execute_command(&self) -> &'static str {
+        match *self {
+            Self::Rust => "cargo test",
+            Self::Node => "npm test",
+            Self::Python => "pytest",
+            Self::Java => "mvn test",
+            Self::Web => "npm run build",
+            Self::Docs => "mkdocs build",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_presets {
+    use std::path::Path;
+
+    use super::*;
+    #[test]
+    fn test_new() {
+        assert_eq!(Preset::new("rust").unwrap(), Preset::Rust);
+        assert!(Preset::new("cobol").is_err());
+    }
+    #[test]
+    fn test_target_extensions() {
+        let is_target = |preset: Preset, path: &str| {
+            preset
+                .target_extensions()
+                .iter()
+                .any(|extension| extension.is_match(Path::new(path)))
+        };
+        assert!(is_target(Preset::Rust, "Cargo.toml"));
+        assert!(!is_target(Preset::Rust, "README.md"));
+        assert!(is_target(Preset::Web, "App.svelte"));
+    }
+}
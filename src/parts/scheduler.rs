@@ -0,0 +1,296 @@
+use std::{collections::VecDeque, io, time::Duration};
+
+use super::executor::{ExecutionResult, OutputChunk, RunningCommand};
+
+/// What to do with a change that arrives while a command is still running.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ConcurrencyPolicy {
+    /// run it after the current command finishes
+    #[default]
+    Queue,
+    /// cancel the running command and run it now
+    Restart,
+    /// ignore it
+    Drop,
+    /// run it now unless max workers are already running, otherwise queue it
+    Parallel(usize),
+}
+impl ConcurrencyPolicy {
+    pub fn new(policy: &str, max_workers: Option<usize>) -> Result<Self, String> {
+        match policy {
+            "queue" => Ok(Self::Queue),
+            "restart" => Ok(Self::Restart),
+            "drop" => Ok(Self::Drop),
+            "parallel" => match max_workers {
+                Some(0) => Err("max_workers must be greater than 0".to_string()),
+                Some(max_workers) => Ok(Self::Parallel(max_workers)),
+                None => Ok(Self::Parallel(Self::DEFAULT_MAX_WORKERS)),
+            },
+            _ => Err(format!(
+                "{} is not concurrency policy. use queue, restart, drop or parallel",
+                policy
+            )),
+        }
+    }
+    const DEFAULT_MAX_WORKERS: usize = 4;
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Queue => "queue",
+            Self::Restart => "restart",
+            Self::Drop => "drop",
+            Self::Parallel(_) => "parallel",
+        }
+    }
+    fn max_workers(&self) -> usize {
+        match *self {
+            Self::Parallel(max_workers) => max_workers,
+            _ => 1,
+        }
+    }
+}
+
+/// Tells the runs of one command apart, from the request until it finishes or is dropped.
+pub type RunId = u64;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ScheduleEvent {
+    Started(RunId, String),
+    Queued(RunId, String),
+    Dropped(RunId, String),
+    Output(String, OutputChunk),
+    /// also for canceled runs
+    Finished(RunId, ExecutionResult),
+}
+
+/// Runs execute commands in the background according to a `ConcurrencyPolicy`.
+///
+/// Nothing here blocks: `request` starts or holds back a command and `poll` collects
+/// the finished ones, so the monitaring loop keeps scanning while commands run.
+pub struct MoniScheduler {
+    policy: ConcurrencyPolicy,
+    timeout: Option<Duration>,
+    running: Vec<(RunId, RunningCommand)>,
+    pending: VecDeque<(RunId, String)>,
+    next_id: RunId,
+}
+impl MoniScheduler {
+    pub fn new(policy: ConcurrencyPolicy, timeout: Option<Duration>) -> Self {
+        Self {
+            policy,
+            timeout,
+            running: Vec::new(),
+            pending: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+    pub fn policy(&self) -> ConcurrencyPolicy {
+        self.policy
+    }
+    pub fn is_busy(&self) -> bool {
+        !self.running.is_empty() || !self.pending.is_empty()
+    }
+    /// Returns the id of the run `command` became, which the events about it carry.
+    pub fn request(&mut self, command: String) -> io::Result<(RunId, Vec<ScheduleEvent>)> {
+        let mut id = self.next_id;
+        self.next_id += 1;
+        let mut events = Vec::new();
+        if self.running.len() < self.policy.max_workers() {
+            events.push(self.start(id, command)?);
+            return Ok((id, events));
+        }
+        match self.policy {
+            ConcurrencyPolicy::Queue | ConcurrencyPolicy::Parallel(_) => {
+                // the same command waiting twice would only run the same thing twice
+                match self.pending.iter().find(|(_, pending)| *pending == command) {
+                    Some((pending_id, _)) => id = *pending_id,
+                    None => self.pending.push_back((id, command.clone())),
+                }
+                events.push(ScheduleEvent::Queued(id, command));
+            }
+            ConcurrencyPolicy::Restart => {
+                events.append(&mut self.cancel_running()?);
+                events.push(self.start(id, command)?);
+            }
+            ConcurrencyPolicy::Drop => events.push(ScheduleEvent::Dropped(id, command)),
+        }
+        Ok((id, events))
+    }
+    /// Collects finished commands and starts queued ones in the freed slots.
+    pub fn poll(&mut self) -> io::Result<Vec<ScheduleEvent>> {
+        let mut events = Vec::new();
+        let mut i = 0;
+        while i < self.running.len() {
+            let (id, running) = &mut self.running[i];
+            let id = *id;
+            let finished = running.try_finish()?;
+            let command = running.command().to_string();
+            running.take_output().into_iter().for_each(|chunk| {
+                events.push(ScheduleEvent::Output(command.clone(), chunk));
+            });
+            match finished {
+                Some(result) => {
+                    self.running.remove(i);
+                    events.push(ScheduleEvent::Finished(id, result));
+                }
+                None => i += 1,
+            }
+        }
+        while self.running.len() < self.policy.max_workers() {
+            match self.pending.pop_front() {
+                Some((id, command)) => events.push(self.start(id, command)?),
+                None => break,
+            }
+        }
+        Ok(events)
+    }
+    /// Cancels running commands and drops queued ones, e.g. when moni stops.
+    pub fn cancel_all(&mut self) -> io::Result<Vec<ScheduleEvent>> {
+        let mut events = self
+            .pending
+            .drain(..)
+            .map(|(id, command)| ScheduleEvent::Dropped(id, command))
+            .collect::<Vec<_>>();
+        events.append(&mut self.cancel_running()?);
+        Ok(events)
+    }
+    /// Cancels every running command, even after one fails, and returns the first error.
+    fn cancel_running(&mut self) -> io::Result<Vec<ScheduleEvent>> {
+        let mut events = Vec::new();
+        let mut first_error = None;
+        for (id, running) in self.running.drain(..) {
+            match running.cancel() {
+                Ok(result) => events.push(ScheduleEvent::Finished(id, result)),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(events),
+        }
+    }
+    fn start(&mut self, id: RunId, command: String) -> io::Result<ScheduleEvent> {
+        self.running
+            .push((id, RunningCommand::spawn(&command, self.timeout)?));
+        Ok(ScheduleEvent::Started(id, command))
+    }
+}
+
+#[cfg(test)]
+mod test_scheduler {
+    use std::thread;
+
+    use super::*;
+    use crate::parts::executor::ExecuteStatus;
+
+    fn poll_until_idle(scheduler: &mut MoniScheduler) -> Vec<ScheduleEvent> {
+        let mut events = Vec::new();
+        while scheduler.is_busy() {
+            events.append(&mut scheduler.poll().unwrap());
+            thread::sleep(Duration::from_millis(10));
+        }
+        events
+    }
+    fn started(events: &[ScheduleEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ScheduleEvent::Started(_, command) => Some(command.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+    #[test]
+    fn test_new_policy() {
+        assert_eq!(
+            ConcurrencyPolicy::new("queue", None).unwrap(),
+            ConcurrencyPolicy::Queue
+        );
+        assert_eq!(
+            ConcurrencyPolicy::new("parallel", Some(2)).unwrap(),
+            ConcurrencyPolicy::Parallel(2)
+        );
+        assert!(ConcurrencyPolicy::new("parallel", Some(0)).is_err());
+        assert!(ConcurrencyPolicy::new("other", None).is_err());
+    }
+    #[test]
+    fn test_queue_runs_once_more_after_current() {
+        let mut scheduler = MoniScheduler::new(ConcurrencyPolicy::Queue, None);
+        scheduler.request("sleep 0.2".to_string()).unwrap();
+        let (id, events) = scheduler.request("echo 1".to_string()).unwrap();
+        assert_eq!(
+            events,
+            vec![ScheduleEvent::Queued(id, "echo 1".to_string())]
+        );
+        // the same run as the one already waiting
+        assert_eq!(scheduler.request("echo 1".to_string()).unwrap().0, id);
+        let events = poll_until_idle(&mut scheduler);
+        assert_eq!(started(&events), vec!["echo 1"]);
+        assert!(events.contains(&ScheduleEvent::Started(id, "echo 1".to_string())));
+    }
+    #[test]
+    fn test_restart_cancels_running() {
+        let mut scheduler = MoniScheduler::new(ConcurrencyPolicy::Restart, None);
+        let (canceled_id, _) = scheduler.request("sleep 10".to_string()).unwrap();
+        let (id, events) = scheduler.request("echo 1".to_string()).unwrap();
+        match &events[0] {
+            ScheduleEvent::Finished(finished_id, result) => {
+                assert_eq!(*finished_id, canceled_id);
+                assert_eq!(result.status(), ExecuteStatus::Canceled)
+            }
+            event => panic!("{:?} is not finished event", event),
+        }
+        assert_eq!(events[1], ScheduleEvent::Started(id, "echo 1".to_string()));
+        poll_until_idle(&mut scheduler);
+    }
+    #[test]
+    fn test_drop_ignores_while_busy() {
+        let mut scheduler = MoniScheduler::new(ConcurrencyPolicy::Drop, None);
+        scheduler.request("sleep 0.2".to_string()).unwrap();
+        let (id, events) = scheduler.request("echo 1".to_string()).unwrap();
+        assert_eq!(
+            events,
+            vec![ScheduleEvent::Dropped(id, "echo 1".to_string())]
+        );
+        let events = poll_until_idle(&mut scheduler);
+        assert!(started(&events).is_empty());
+    }
+    #[test]
+    fn test_parallel_limits_workers() {
+        let mut scheduler = MoniScheduler::new(ConcurrencyPolicy::Parallel(2), None);
+        scheduler.request("sleep 0.2".to_string()).unwrap();
+        let (id, events) = scheduler.request("sleep 0.1".to_string()).unwrap();
+        assert_eq!(
+            events,
+            vec![ScheduleEvent::Started(id, "sleep 0.1".to_string())]
+        );
+        let (id, events) = scheduler.request("echo 1".to_string()).unwrap();
+        assert_eq!(
+            events,
+            vec![ScheduleEvent::Queued(id, "echo 1".to_string())]
+        );
+        let events = poll_until_idle(&mut scheduler);
+        assert_eq!(started(&events), vec!["echo 1"]);
+    }
+    #[test]
+    fn test_cancel_all() {
+        let mut scheduler = MoniScheduler::new(ConcurrencyPolicy::Parallel(2), None);
+        scheduler.request("sleep 10".to_string()).unwrap();
+        scheduler.request("sleep 11".to_string()).unwrap();
+        let (queued_id, _) = scheduler.request("sleep 12".to_string()).unwrap();
+        let events = scheduler.cancel_all().unwrap();
+        assert_eq!(
+            events[0],
+            ScheduleEvent::Dropped(queued_id, "sleep 12".to_string())
+        );
+        let canceled = events
+            .iter()
+            .filter(|event| {
+                matches!(event, ScheduleEvent::Finished(_, result) if result.status() == ExecuteStatus::Canceled)
+            })
+            .count();
+        assert_eq!(canceled, 2);
+        assert!(scheduler.running.is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Where MoniDebuger writes its lines.
+pub trait MoniSink: Write {
+    /// Whether a person is reading the sink, e.g. to decide if escape codes are fine.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdoutSink;
+impl Write for StdoutSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stdout().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+impl MoniSink for StdoutSink {
+    fn is_terminal(&self) -> bool {
+        io::stdout().is_terminal()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StderrSink;
+impl Write for StderrSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+impl MoniSink for StderrSink {
+    fn is_terminal(&self) -> bool {
+        io::stderr().is_terminal()
+    }
+}
+
+#[derive(Debug)]
+pub struct FileSink {
+    file: File,
+}
+impl FileSink {
+    /// Appends to `filepath`, so restarting moni keeps the earlier log.
+    pub fn new<P: AsRef<Path>>(filepath: P) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filepath)?;
+        Ok(Self { file })
+    }
+}
+impl Write for FileSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+impl MoniSink for FileSink {}
+
+/// Keeps everything in memory. Clones share the buffer, so keep one to read what was written.
+#[derive(Debug, Default, Clone)]
+pub struct BufferSink {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+impl BufferSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.lock().unwrap()).to_string()
+    }
+}
+impl Write for BufferSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl MoniSink for BufferSink {}
+
+/// Writes to every sink, e.g. to the terminal and a log file at once.
+#[derive(Default)]
+pub struct TeeSink {
+    sinks: Vec<Box<dyn MoniSink>>,
+}
+impl TeeSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn sink<S: MoniSink + 'static>(mut self, sink: S) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+    pub fn add_sink(&mut self, sink: Box<dyn MoniSink>) {
+        self.sinks.push(sink);
+    }
+}
+impl Write for TeeSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for sink in self.sinks.iter_mut() {
+            sink.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        for sink in self.sinks.iter_mut() {
+            sink.flush()?;
+        }
+        Ok(())
+    }
+}
+impl MoniSink for TeeSink {
+    // escape codes for the terminal would end up in the other sinks too
+    fn is_terminal(&self) -> bool {
+        !self.sinks.is_empty() && self.sinks.iter().all(|sink| sink.is_terminal())
+    }
+}
+
+#[cfg(test)]
+mod test_sink {
+    use super::*;
+    #[test]
+    fn test_tee_sink_writes_every_sink() {
+        let first = BufferSink::new();
+        let second = BufferSink::new();
+        let mut tee = TeeSink::new().sink(first.clone()).sink(second.clone());
+        writeln!(tee, "hello").unwrap();
+        assert_eq!(first.contents(), "hello\n");
+        assert_eq!(second.contents(), "hello\n");
+        assert!(!tee.is_terminal());
+    }
+}
@@ -0,0 +1,101 @@
+use std::{collections::HashMap, time::Duration};
+
+use super::executor::{ExecuteStatus, ExecutionResult};
+
+/// Counts of the current session, e.g. to notice that the test suite got slower.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MoniStats {
+    runs: usize,
+    failures: usize,
+    total_elapsed: Duration,
+    max_elapsed: Duration,
+    triggers: HashMap<String, usize>,
+}
+impl MoniStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Canceled executions did not finish, so they are not counted.
+    pub fn record(&mut self, result: &ExecutionResult) {
+        if result.status() == ExecuteStatus::Canceled {
+            return;
+        }
+        self.runs += 1;
+        if !result.is_success() {
+            self.failures += 1;
+        }
+        self.total_elapsed += result.elapsed();
+        self.max_elapsed = self.max_elapsed.max(result.elapsed());
+    }
+    pub fn record_trigger(&mut self, filepath: &str) {
+        *self.triggers.entry(filepath.to_string()).or_insert(0) += 1;
+    }
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+    pub fn failures(&self) -> usize {
+        self.failures
+    }
+    pub fn average_elapsed(&self) -> Duration {
+        if self.runs == 0 {
+            return Duration::ZERO;
+        }
+        self.total_elapsed / self.runs as u32
+    }
+    pub fn max_elapsed(&self) -> Duration {
+        self.max_elapsed
+    }
+    /// The `n` files that triggered most executions, most first.
+    pub fn top_triggers(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut triggers = self
+            .triggers
+            .iter()
+            .map(|(path, count)| (path.as_str(), *count))
+            .collect::<Vec<_>>();
+        triggers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        triggers.truncate(n);
+        triggers
+    }
+}
+
+/// e.g. `850ms` or `1.23s`
+pub fn format_elapsed(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) {
+        format!("{}ms", elapsed.as_millis())
+    } else {
+        format!("{:.2}s", elapsed.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod test_stats {
+    use super::*;
+    fn result(status: &Result<String, String>, millis: u64) -> ExecutionResult {
+        ExecutionResult::from_fn_result("test.rs", status, Duration::from_millis(millis))
+    }
+    #[test]
+    fn test_record() {
+        let mut stats = MoniStats::new();
+        assert_eq!(stats.average_elapsed(), Duration::ZERO);
+        stats.record(&result(&Ok("ok".to_string()), 100));
+        stats.record(&result(&Err("error".to_string()), 300));
+        assert_eq!(stats.runs(), 2);
+        assert_eq!(stats.failures(), 1);
+        assert_eq!(stats.average_elapsed(), Duration::from_millis(200));
+        assert_eq!(stats.max_elapsed(), Duration::from_millis(300));
+    }
+    #[test]
+    fn test_top_triggers() {
+        let mut stats = MoniStats::new();
+        stats.record_trigger("b.rs");
+        stats.record_trigger("a.rs");
+        stats.record_trigger("c.rs");
+        stats.record_trigger("c.rs");
+        assert_eq!(stats.top_triggers(2), vec![("c.rs", 2), ("a.rs", 1)]);
+    }
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_elapsed(Duration::from_millis(1234)), "1.23s");
+    }
+}
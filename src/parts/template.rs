@@ -0,0 +1,164 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::stats::format_elapsed;
+
+/// Values of `{command}`, `{file}`, `{exit_code}`, `{duration}`, `{time}`, `{run_number}`
+/// and `{rule}` in debug messages. Values that are not known yet expand to nothing.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MessageVars {
+    command: Option<String>,
+    file: Option<String>,
+    exit_code: Option<i32>,
+    duration: Option<Duration>,
+    run_number: usize,
+    rule: Option<String>,
+}
+impl MessageVars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn set_rule(&mut self, rule: &str) {
+        self.rule = Some(rule.to_string());
+    }
+    /// `{rule}`, or moni when no rule is set.
+    pub fn rule_name(&self) -> &str {
+        self.rule.as_deref().unwrap_or("moni")
+    }
+    /// Counts a new run, so the result of the previous run is forgotten.
+    pub fn start_run(&mut self, command: &str, file: Option<&str>) {
+        self.run_number += 1;
+        self.command = Some(command.to_string());
+        self.file = file.map(|file| file.to_string());
+        self.exit_code = None;
+        self.duration = None;
+    }
+    pub fn finish_run(&mut self, command: &str, exit_code: Option<i32>, duration: Duration) {
+        self.command = Some(command.to_string());
+        self.exit_code = exit_code;
+        self.duration = Some(duration);
+    }
+    /// Unknown names, e.g. `{}` of `find -exec {}`, are left as they are.
+    pub fn render(&self, template: &str) -> String {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            let after_open = &rest[open..];
+            match after_open.find('}').and_then(|close| {
+                self.value(&after_open[1..close])
+                    .map(|value| (value, close))
+            }) {
+                Some((value, close)) => {
+                    rendered.push_str(&value);
+                    rest = &after_open[close + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = &after_open[1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+    /// `render` that leaves each `literal` in `template` as it is, so a command like
+    /// `echo {file}` put in a message is printed as it ran.
+    pub fn render_around(&self, template: &str, literal: &str) -> String {
+        if literal.is_empty() {
+            return self.render(template);
+        }
+        template
+            .split(literal)
+            .map(|part| self.render(part))
+            .collect::<Vec<_>>()
+            .join(literal)
+    }
+    fn value(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "command" => self.command.clone().unwrap_or_default(),
+            "file" => self.file.clone().unwrap_or_default(),
+            "exit_code" => self
+                .exit_code
+                .map(|exit_code| exit_code.to_string())
+                .unwrap_or_default(),
+            "duration" => self.duration.map(format_elapsed).unwrap_or_default(),
+            "time" => now(),
+            "run_number" => self.run_number.to_string(),
+            "rule" => self.rule.clone().unwrap_or_default(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Local time as `HH:MM:SS`.
+fn now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default();
+    let secs_of_day = (secs as i64 + utc_offset_secs(secs as i64)).rem_euclid(24 * 60 * 60);
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+#[cfg(unix)]
+fn utc_offset_secs(secs: i64) -> i64 {
+    let time = secs as libc::time_t;
+    // SAFETY: localtime_r only writes to the tm it is given
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+#[cfg(not(unix))]
+fn utc_offset_secs(_: i64) -> i64 {
+    0
+}
+
+#[cfg(test)]
+mod test_template {
+    use super::*;
+    #[test]
+    fn test_render() {
+        let mut vars = MessageVars::new();
+        vars.set_rule("tests");
+        vars.start_run("cargo test", Some("./src/lib.rs"));
+        assert_eq!(
+            vars.render("[{rule}] #{run_number} {command} {file}{exit_code}"),
+            "[tests] #1 cargo test ./src/lib.rs"
+        );
+        vars.finish_run("cargo test", Some(101), Duration::from_millis(1500));
+        assert_eq!(
+            vars.render(" exit {exit_code} in {duration} "),
+            " exit 101 in 1.50s "
+        );
+    }
+    #[test]
+    fn test_render_keeps_unknown() {
+        let vars = MessageVars::new();
+        assert_eq!(
+            vars.render("find -exec {} {other} {"),
+            "find -exec {} {other} {"
+        );
+        assert_eq!(vars.render("{time}").len(), "00:00:00".len());
+    }
+    #[test]
+    fn test_render_around() {
+        let mut vars = MessageVars::new();
+        vars.start_run("echo {file} ${HOME}", Some("./src/lib.rs"));
+        assert_eq!(
+            vars.render_around(
+                " {file} execute echo {file} ${HOME} ",
+                "echo {file} ${HOME}"
+            ),
+            " ./src/lib.rs execute echo {file} ${HOME} "
+        );
+    }
+}
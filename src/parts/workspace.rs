@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use super::filesearcher::FileSearcherBuilder;
+
+/// One root directory of a Moni. Filters that are set replace the ones of the Moni
+/// for this root, the rest are shared.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Workspace<'a> {
+    root: &'a Path,
+    target_extensions: Option<Vec<&'a str>>,
+    ignore_filenames: Option<Vec<&'a str>>,
+    ignore_extensions: Option<Vec<&'a str>>,
+    ignore_path_words: Option<Vec<&'a str>>,
+    include_path_words: Option<Vec<&'a str>>,
+}
+impl<'a> Workspace<'a> {
+    pub fn new<P: AsRef<Path> + ?Sized>(root: &'a P) -> Self {
+        Self {
+            root: root.as_ref(),
+            target_extensions: None,
+            ignore_filenames: None,
+            ignore_extensions: None,
+            ignore_path_words: None,
+            include_path_words: None,
+        }
+    }
+    pub fn root(&self) -> &'a Path {
+        self.root
+    }
+    pub fn target_extensions(mut self, target_extensions: Vec<&'a str>) -> Self {
+        self.target_extensions = Some(target_extensions);
+        self
+    }
+    pub fn ignore_filenames(mut self, filenames: Vec<&'a str>) -> Self {
+        self.ignore_filenames = Some(filenames);
+        self
+    }
+    pub fn ignore_extensions(mut self, extensions: Vec<&'a str>) -> Self {
+        self.ignore_extensions = Some(extensions);
+        self
+    }
+    pub fn ignore_path_words(mut self, re: Vec<&'a str>) -> Self {
+        self.ignore_path_words = Some(re);
+        self
+    }
+    pub fn include_path_words(mut self, re: Vec<&'a str>) -> Self {
+        self.include_path_words = Some(re);
+        self
+    }
+    /// `shared` with the root and the filters of this workspace.
+    pub fn apply(&self, mut shared: FileSearcherBuilder<'a>) -> FileSearcherBuilder<'a> {
+        shared.set_root(self.root);
+        if let Some(target_extensions) = &self.target_extensions {
+            shared.set_target_extensions(target_extensions.clone());
+        }
+        if let Some(filenames) = &self.ignore_filenames {
+            shared.set_ignore_files(filenames.clone());
+        }
+        if let Some(extensions) = &self.ignore_extensions {
+            shared.set_ignore_extensions(extensions.clone());
+        }
+        if let Some(re) = &self.ignore_path_words {
+            shared.set_ignore_re(re.clone());
+        }
+        if let Some(re) = &self.include_path_words {
+            shared.set_include_re(re.clone());
+        }
+        shared
+    }
+}
+
+/// The root `filepath` was found under, the deepest one when roots are nested.
+pub fn find_root<'r>(roots: &[&'r Path], filepath: &Path) -> Option<&'r Path> {
+    roots
+        .iter()
+        .filter(|root| filepath.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .copied()
+}
+/// `filepath` from its root, e.g. `lib.rs` of `./src/lib.rs` under `./src`.
+pub fn relative_path<'p>(root: &Path, filepath: &'p Path) -> &'p Path {
+    filepath.strip_prefix(root).unwrap_or(filepath)
+}
+
+#[cfg(test)]
+mod test_workspace {
+    use super::*;
+    #[test]
+    fn test_find_root() {
+        let roots = ["./", "./src", "../shared-proto"].map(Path::new);
+        assert_eq!(
+            find_root(&roots, Path::new("./src/lib.rs")),
+            Some(Path::new("./src"))
+        );
+        assert_eq!(
+            find_root(&roots, Path::new("./README.md")),
+            Some(Path::new("./"))
+        );
+        assert_eq!(
+            find_root(&roots, Path::new("../shared-proto/a.proto")),
+            Some(Path::new("../shared-proto"))
+        );
+        assert_eq!(
+            find_root(&[Path::new("./src")], Path::new("./srcs/lib.rs")),
+            None
+        );
+    }
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("./src"), Path::new("./src/parts/moni.rs")),
+            Path::new("parts/moni.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("./"), Path::new("./README.md")),
+            Path::new("README.md")
+        );
+    }
+    #[test]
+    fn test_apply() {
+        let filesearcher = Workspace::new("./tests/test2")
+            .target_extensions(vec!["txt"])
+            .apply(FileSearcherBuilder::new().target_extension("rs"))
+            .build();
+        assert_eq!(filesearcher.root(), Path::new("./tests/test2"));
+        assert_eq!(
+            filesearcher.get_all_filenames(),
+            vec!["./tests/test2/test2.txt"]
+        );
+    }
+}